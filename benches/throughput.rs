@@ -0,0 +1,89 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use redis_clone::{config::Config, server::Server};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Notify,
+};
+
+const CONNECTIONS: [usize; 3] = [1, 8, 64];
+const REQUESTS_PER_RUN: usize = 64_000;
+
+async fn roundtrip(stream: &mut TcpStream, request: &[u8], reply_len: usize, buf: &mut [u8]) {
+    stream.write_all(request).await.unwrap();
+    stream.read_exact(&mut buf[..reply_len]).await.unwrap();
+}
+
+async fn run_client(addr: String, id: usize, requests: usize) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.set_nodelay(true).unwrap();
+    let key = format!("key:{:04}", id);
+    let set = format!(
+        "*3\r\n$3\r\nSET\r\n${}\r\n{}\r\n$5\r\nvalue\r\n",
+        key.len(),
+        key
+    );
+    let get = format!("*2\r\n$3\r\nGET\r\n${}\r\n{}\r\n", key.len(), key);
+    let mut buf = [0; 64];
+
+    for _ in 0..requests / 2 {
+        roundtrip(&mut stream, set.as_bytes(), b"+OK\r\n".len(), &mut buf).await;
+        roundtrip(
+            &mut stream,
+            get.as_bytes(),
+            b"$5\r\nvalue\r\n".len(),
+            &mut buf,
+        )
+        .await;
+    }
+}
+
+async fn measure(addr: &str, connections: usize) -> Duration {
+    let requests = REQUESTS_PER_RUN / connections;
+    let started = Instant::now();
+    let clients: Vec<_> = (0..connections)
+        .map(|id| tokio::spawn(run_client(addr.to_string(), id, requests)))
+        .collect();
+    for client in clients {
+        client.await.unwrap();
+    }
+    started.elapsed()
+}
+
+#[tokio::main]
+async fn main() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = Config {
+        port,
+        max_clients: CONNECTIONS.iter().sum(),
+        ..Config::default()
+    };
+    let addr = config.addr();
+
+    let server = Server::new(config);
+    let ready = Arc::new(Notify::new());
+    let shutdown = Arc::new(Notify::new());
+    let server = {
+        let (ready, shutdown) = (ready.clone(), shutdown.clone());
+        tokio::spawn(async move { server.listen_with_shutdown(ready, shutdown).await })
+    };
+    ready.notified().await;
+
+    println!("{:>12} {:>12} {:>14}", "connections", "requests", "ops/sec");
+    for connections in CONNECTIONS {
+        let elapsed = measure(&addr, connections).await;
+        let ops = REQUESTS_PER_RUN as f64 / elapsed.as_secs_f64();
+        println!("{:>12} {:>12} {:>14.0}", connections, REQUESTS_PER_RUN, ops);
+    }
+
+    shutdown.notify_one();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,293 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use crate::{
+    commands::dispatch_command,
+    config::AppendFsync,
+    connection::Client,
+    parser::{RespMessage, RespParser},
+    server::Shared,
+};
+
+const FSYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+struct AofFile {
+    file: File,
+    db: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct Aof {
+    fsync: AppendFsync,
+    file: Mutex<Option<AofFile>>,
+}
+
+impl Aof {
+    pub fn new(fsync: AppendFsync) -> Self {
+        Aof {
+            fsync,
+            file: Mutex::default(),
+        }
+    }
+
+    pub fn open(&self, path: &Path) -> io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        *self.file.lock().unwrap() = Some(AofFile { file, db: None });
+        Ok(())
+    }
+
+    pub fn append(&self, db: usize, parts: &[Vec<u8>]) -> io::Result<()> {
+        let mut guard = self.file.lock().unwrap();
+        let Some(aof) = guard.as_mut() else {
+            return Ok(());
+        };
+
+        let mut out = Vec::new();
+        if aof.db != Some(db) {
+            encode_command(&[b"SELECT".to_vec(), db.to_string().into_bytes()], &mut out);
+            aof.db = Some(db);
+        }
+        encode_command(parts, &mut out);
+        aof.file.write_all(&out)?;
+
+        if self.fsync == AppendFsync::Always {
+            aof.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    pub fn sync(&self) -> io::Result<()> {
+        match self.file.lock().unwrap().as_ref() {
+            Some(aof) => aof.file.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn spawn_fsync_cycle(shared: &Shared) -> Option<JoinHandle<()>> {
+        if shared.aof.fsync != AppendFsync::EverySec {
+            return None;
+        }
+
+        let aof = shared.aof.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(FSYNC_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = aof.sync() {
+                    error!(error = %e, "AOF fsync failed");
+                }
+            }
+        }))
+    }
+}
+
+fn encode_command(parts: &[Vec<u8>], out: &mut Vec<u8>) {
+    RespMessage::Array(parts.iter().cloned().map(RespMessage::BulkString).collect())
+        .encode_into(out);
+}
+
+pub async fn replay(shared: &Shared, path: &Path) -> io::Result<()> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut parser = RespParser::new(file);
+    let mut client = Client {
+        authenticated: true,
+        ..Client::default()
+    };
+
+    loop {
+        let message = match parser.next_message().await {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                warn!("ignoring truncated command at the end of the AOF");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let (name, args) = message.into_command()?;
+        dispatch_command(shared, &mut client, name, args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::aof::{Aof, replay};
+    use crate::commands::{command, dispatch};
+    use crate::config::AppendFsync;
+    use crate::connection::Client;
+    use crate::server::Shared;
+    use crate::store::{Store, Value};
+
+    #[tokio::test]
+    async fn test_replay_restores_state() {
+        let path = std::env::temp_dir().join(format!("redis-clone-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let shared = Shared {
+            aof: Arc::new(Aof::new(AppendFsync::Always)),
+            ..Shared::default()
+        };
+        shared.aof.open(&path).unwrap();
+        let mut client = Client::default();
+        for cmd in [
+            &["SET", "foo", "bar"][..],
+            &["SET", "gone", "x"],
+            &["DEL", "gone"],
+            &["INCR", "counter"],
+            &["GET", "foo"],
+            &["INCR", "foo"],
+            &["SELECT", "1"],
+            &["RPUSH", "list", "a", "b"],
+        ] {
+            dispatch(&shared, &mut client, command(cmd));
+        }
+        drop(shared);
+
+        let restored = Shared::default();
+        replay(&restored, &path).await.unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            restored.store.lock(0).get(b"foo"),
+            Some(&Value::String(b"bar".to_vec()))
+        );
+        assert!(!restored.store.lock(0).exists(b"gone"));
+        assert_eq!(
+            restored.store.lock(0).get(b"counter"),
+            Some(&Value::String(b"1".to_vec()))
+        );
+        assert_eq!(
+            restored.store.lock(1).get(b"list"),
+            Some(&Value::List([b"a".to_vec(), b"b".to_vec()].into()))
+        );
+        assert!(!String::from_utf8_lossy(&contents).contains("GET"));
+    }
+
+    #[tokio::test]
+    async fn test_reopened_aof_selects_db_before_first_write() {
+        let path =
+            std::env::temp_dir().join(format!("redis-clone-reopen-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let shared = Shared {
+            aof: Arc::new(Aof::new(AppendFsync::Always)),
+            ..Shared::default()
+        };
+        shared.aof.open(&path).unwrap();
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["SELECT", "3"]));
+        dispatch(&shared, &mut client, command(&["SET", "three", "x"]));
+        drop(shared);
+
+        let reopened = Shared {
+            aof: Arc::new(Aof::new(AppendFsync::Always)),
+            ..Shared::default()
+        };
+        replay(&reopened, &path).await.unwrap();
+        reopened.aof.open(&path).unwrap();
+        dispatch(
+            &reopened,
+            &mut Client::default(),
+            command(&["SET", "zero", "x"]),
+        );
+        drop(reopened);
+
+        let restored = Shared::default();
+        replay(&restored, &path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(restored.store.lock(3).exists(b"three"));
+        assert!(restored.store.lock(0).exists(b"zero"));
+        assert!(!restored.store.lock(3).exists(b"zero"));
+    }
+
+    #[tokio::test]
+    async fn test_spop_is_logged_as_srem() {
+        let path =
+            std::env::temp_dir().join(format!("redis-clone-spop-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let shared = Shared {
+            aof: Arc::new(Aof::new(AppendFsync::Always)),
+            ..Shared::default()
+        };
+        shared.aof.open(&path).unwrap();
+        let mut client = Client::default();
+        dispatch(
+            &shared,
+            &mut client,
+            command(&["SADD", "set", "a", "b", "c"]),
+        );
+        dispatch(&shared, &mut client, command(&["SPOP", "set", "2"]));
+        dispatch(&shared, &mut client, command(&["SPOP", "missing"]));
+        let remaining = shared.store.lock(0).get(b"set").cloned();
+        drop(shared);
+
+        let restored = Shared::default();
+        replay(&restored, &path).await.unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.store.lock(0).get(b"set").cloned(), remaining);
+        assert!(!String::from_utf8_lossy(&contents).contains("SPOP"));
+    }
+
+    #[tokio::test]
+    async fn test_ttls_are_logged_as_deadlines() {
+        let path = std::env::temp_dir().join(format!("redis-clone-ttl-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let aof = Arc::new(Aof::new(AppendFsync::Always));
+        let shared = Shared {
+            store: Store::default().with_aof(aof.clone()),
+            aof,
+            ..Shared::default()
+        };
+        shared.aof.open(&path).unwrap();
+        let mut client = Client::default();
+        for cmd in [
+            &["SET", "expire", "x"][..],
+            &["EXPIRE", "expire", "100"],
+            &["EXPIRE", "missing", "100"],
+            &["SETEX", "setex", "100", "x"],
+            &["SET", "set", "x", "NX", "EX", "100"],
+            &["SET", "lazy", "x", "PX", "10"],
+            &["SET", "active", "x", "PX", "10"],
+        ] {
+            dispatch(&shared, &mut client, command(cmd));
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        dispatch(&shared, &mut client, command(&["GET", "lazy"]));
+        let expiry_cycle = shared.store.spawn_expiry_cycle(Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        expiry_cycle.abort();
+        drop(shared);
+
+        let restored = Shared::default();
+        replay(&restored, &path).await.unwrap();
+        let contents = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for key in [&b"expire"[..], b"setex", b"set"] {
+            let ttl = restored.store.lock(0).ttl(key).unwrap().unwrap();
+            assert!(ttl > Duration::from_secs(99) && ttl <= Duration::from_secs(100));
+        }
+        for relative in ["$6\r\nEXPIRE\r\n", "SETEX", "$2\r\nEX\r\n", "$2\r\nPX\r\n"] {
+            assert!(!contents.contains(relative));
+        }
+        assert!(contents.contains("PEXPIREAT") && contents.contains("PXAT"));
+        assert!(contents.contains("$3\r\nDEL\r\n$4\r\nlazy\r\n"));
+        assert!(contents.contains("$3\r\nDEL\r\n$6\r\nactive\r\n"));
+    }
+}
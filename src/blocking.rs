@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+
+type Waiters = HashMap<(usize, Vec<u8>), Vec<Arc<Notify>>>;
+
+#[derive(Clone, Default)]
+pub struct Blocking {
+    waiters: Arc<Mutex<Waiters>>,
+}
+
+pub struct Waiter {
+    blocking: Blocking,
+    db: usize,
+    keys: Vec<Vec<u8>>,
+    notify: Arc<Notify>,
+}
+
+impl Blocking {
+    pub fn new() -> Self {
+        Blocking::default()
+    }
+
+    pub fn wait_for(&self, db: usize, keys: &[Vec<u8>]) -> Waiter {
+        let notify = Arc::new(Notify::new());
+        let mut waiters = self.waiters.lock().unwrap();
+        for key in keys {
+            waiters
+                .entry((db, key.clone()))
+                .or_default()
+                .push(notify.clone());
+        }
+
+        Waiter {
+            blocking: self.clone(),
+            db,
+            keys: keys.to_vec(),
+            notify,
+        }
+    }
+
+    pub fn wake(&self, db: usize, key: &[u8]) {
+        let waiters = self.waiters.lock().unwrap();
+        if let Some(notifies) = waiters.get(&(db, key.to_vec())) {
+            for notify in notifies {
+                notify.notify_one();
+            }
+        }
+    }
+
+    pub fn blocked(&self, db: usize, key: &[u8]) -> usize {
+        self.waiters
+            .lock()
+            .unwrap()
+            .get(&(db, key.to_vec()))
+            .map_or(0, Vec::len)
+    }
+}
+
+impl Waiter {
+    pub async fn woken(&self) {
+        self.notify.notified().await;
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        let mut waiters = self.blocking.waiters.lock().unwrap();
+        for key in &self.keys {
+            let slot = (self.db, key.clone());
+            let Some(notifies) = waiters.get_mut(&slot) else {
+                continue;
+            };
+            notifies.retain(|notify| !Arc::ptr_eq(notify, &self.notify));
+            if notifies.is_empty() {
+                waiters.remove(&slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::blocking::Blocking;
+
+    #[tokio::test]
+    async fn test_wake_before_wait_is_not_lost() {
+        let blocking = Blocking::new();
+        let waiter = blocking.wait_for(0, &[b"list".to_vec()]);
+        blocking.wake(0, b"list");
+
+        tokio::time::timeout(Duration::from_secs(1), waiter.woken())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wake_only_matching_key() {
+        let blocking = Blocking::new();
+        let waiter = blocking.wait_for(0, &[b"list".to_vec()]);
+        blocking.wake(0, b"other");
+        blocking.wake(1, b"list");
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), waiter.woken())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_dropped_waiter_is_unregistered() {
+        let blocking = Blocking::new();
+        let waiter = blocking.wait_for(0, &[b"a".to_vec(), b"b".to_vec()]);
+        let _other = blocking.wait_for(0, &[b"a".to_vec()]);
+        assert_eq!(blocking.blocked(0, b"a"), 2);
+
+        drop(waiter);
+        assert_eq!(blocking.blocked(0, b"a"), 1);
+        assert_eq!(blocking.blocked(0, b"b"), 0);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+struct ClientInfo {
+    addr: String,
+    name: Option<Vec<u8>>,
+    db: usize,
+    connected_at: Instant,
+}
+
+#[derive(Clone, Default)]
+pub struct Clients {
+    connected: Arc<Mutex<BTreeMap<u64, ClientInfo>>>,
+}
+
+pub struct Registration {
+    clients: Clients,
+    id: u64,
+}
+
+impl Clients {
+    pub fn new() -> Self {
+        Clients::default()
+    }
+
+    pub fn register(&self, id: u64, addr: String) -> Registration {
+        let info = ClientInfo {
+            addr,
+            name: None,
+            db: 0,
+            connected_at: Instant::now(),
+        };
+        self.connected.lock().unwrap().insert(id, info);
+
+        Registration {
+            clients: self.clone(),
+            id,
+        }
+    }
+
+    pub fn set_name(&self, id: u64, name: Option<Vec<u8>>) {
+        if let Some(info) = self.connected.lock().unwrap().get_mut(&id) {
+            info.name = name;
+        }
+    }
+
+    pub fn set_db(&self, id: u64, db: usize) {
+        if let Some(info) = self.connected.lock().unwrap().get_mut(&id) {
+            info.db = db;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.connected.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn list(&self) -> String {
+        let mut list = String::new();
+        for (id, info) in self.connected.lock().unwrap().iter() {
+            let _ = writeln!(
+                list,
+                "id={} addr={} name={} age={} db={}",
+                id,
+                info.addr,
+                String::from_utf8_lossy(info.name.as_deref().unwrap_or_default()),
+                info.connected_at.elapsed().as_secs(),
+                info.db
+            );
+        }
+        list
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.clients.connected.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::Clients;
+
+    #[test]
+    fn test_registration_is_removed_on_drop() {
+        let clients = Clients::new();
+        let first = clients.register(1, "127.0.0.1:5000".to_string());
+        let _second = clients.register(2, "127.0.0.1:5001".to_string());
+
+        assert_eq!(clients.len(), 2);
+        drop(first);
+
+        assert_eq!(clients.len(), 1);
+        assert!(clients.list().starts_with("id=2 addr=127.0.0.1:5001 "));
+    }
+
+    #[test]
+    fn test_list_reports_name_and_db() {
+        let clients = Clients::new();
+        let _registration = clients.register(7, "127.0.0.1:5000".to_string());
+
+        clients.set_name(7, Some(b"worker".to_vec()));
+        clients.set_db(7, 3);
+
+        let list = clients.list();
+        assert!(list.starts_with("id=7 addr=127.0.0.1:5000 name=worker age="));
+        assert!(list.ends_with(" db=3\n"));
+    }
+}
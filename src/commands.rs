@@ -0,0 +1,721 @@
+mod connection;
+mod hashes;
+mod keys;
+mod lists;
+mod server;
+mod sets;
+mod strings;
+pub(crate) mod table;
+mod transactions;
+mod zsets;
+
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    commands::table::{Command, Handler, commands},
+    config::MaxmemoryPolicy,
+    connection::Client,
+    glob::glob_match,
+    parser::RespMessage,
+    server::Shared,
+};
+
+const WRONG_TYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+const NOT_A_FLOAT: &str = "ERR value is not a valid float";
+const SYNTAX_ERROR: &str = "ERR syntax error";
+
+const REDIS_VERSION: &str = "7.2.0";
+
+const UNKNOWN_COMMAND_ARGS_LEN: usize = 128;
+
+const OUT_OF_MEMORY: &str = "OOM command not allowed when used memory > 'maxmemory'.";
+
+pub fn dispatch(shared: &Shared, client: &mut Client, message: RespMessage) -> RespMessage {
+    match message.into_command() {
+        Ok((name, args)) => dispatch_command(shared, client, name, args),
+        Err(e) => error(&format!("ERR Protocol error: {}", e)),
+    }
+}
+
+pub fn dispatch_command(
+    shared: &Shared,
+    client: &mut Client,
+    mut name: Vec<u8>,
+    args: Vec<Vec<u8>>,
+) -> RespMessage {
+    name.make_ascii_uppercase();
+    let mut parts = args;
+    parts.insert(0, name);
+    let name = &parts[0];
+
+    let command = commands().get(name);
+
+    if !client.is_authenticated(&shared.config())
+        && !command.is_some_and(|command| command.has_flag(table::NO_AUTH))
+    {
+        return error("NOAUTH Authentication required.");
+    }
+
+    if client.transaction.is_some() && name != b"RESET" {
+        return transactions::queue(shared, client, parts);
+    }
+    let _exec = shared.exec.read().unwrap();
+
+    let (name, args) = parts.split_first().unwrap();
+    let Some(command) = command else {
+        return unknown_command(name, args);
+    };
+    if !command.has_valid_arity(parts.len()) {
+        return command_arity_error(name);
+    }
+    if command.has_flag(table::DENY_OOM) && !reclaim_memory(shared) {
+        return error(OUT_OF_MEMORY);
+    }
+
+    match command.handler {
+        Handler::Server(execute) => {
+            let reply = execute(shared, client, args);
+            propagate(shared, client.db, command, &parts, &reply);
+            reply
+        }
+        Handler::Connection => wrong_context(command),
+        Handler::Keyspace(execute) => {
+            let mut keyspace = command.lock(&shared.store, client.db, args);
+            let reply = execute(shared, &mut keyspace, args);
+            keyspace.log_expired();
+            propagate(shared, client.db, command, &parts, &reply);
+            reply
+        }
+    }
+}
+
+fn reclaim_memory(shared: &Shared) -> bool {
+    let (maxmemory, policy) = {
+        let config = shared.config();
+        (config.maxmemory, config.maxmemory_policy)
+    };
+    if maxmemory == 0 {
+        return true;
+    }
+
+    while shared.store.used_memory() > maxmemory {
+        let mut rng = shared.rng.lock().unwrap();
+        let evicted = match policy {
+            MaxmemoryPolicy::NoEviction => None,
+            MaxmemoryPolicy::AllKeysRandom => shared.store.evict_random(&mut *rng),
+            MaxmemoryPolicy::VolatileTtl => shared.store.evict_volatile_ttl(&mut *rng),
+        };
+        drop(rng);
+
+        let Some((db, key)) = evicted else {
+            return false;
+        };
+        shared.stats.evicted_keys.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = shared.aof.append(db, &[b"DEL".to_vec(), key]) {
+            tracing::error!(error = %e, "AOF write failed");
+        }
+    }
+
+    true
+}
+
+fn propagate(
+    shared: &Shared,
+    db: usize,
+    command: &Command,
+    parts: &[Vec<u8>],
+    reply: &RespMessage,
+) {
+    if !command.has_flag(table::WRITE) || matches!(reply, RespMessage::Error(_)) {
+        return;
+    }
+
+    let rewritten;
+    let parts = match parts[0].as_slice() {
+        b"SPOP" => {
+            let popped = match reply {
+                RespMessage::BulkString(member) => vec![member.clone()],
+                RespMessage::Array(members) => members
+                    .iter()
+                    .filter_map(|member| match member {
+                        RespMessage::BulkString(member) => Some(member.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            if popped.is_empty() {
+                return;
+            }
+            rewritten = [vec![b"SREM".to_vec(), parts[1].clone()], popped].concat();
+            &rewritten
+        }
+        b"BLPOP" | b"BRPOP" => {
+            let RespMessage::Array(popped) = reply else {
+                return;
+            };
+            let Some(RespMessage::BulkString(key)) = popped.first() else {
+                return;
+            };
+            let pop: &[u8] = if parts[0] == b"BLPOP" {
+                b"LPOP"
+            } else {
+                b"RPOP"
+            };
+            rewritten = vec![pop.to_vec(), key.clone()];
+            &rewritten
+        }
+        b"EXPIRE" | b"EXPIREAT" | b"PEXPIREAT" => {
+            if *reply != RespMessage::Integer(1) {
+                return;
+            }
+            let amount = parse_integer(&parts[2]).unwrap_or_default();
+            let deadline = match parts[0].as_slice() {
+                b"EXPIRE" => unix_millis().saturating_add(amount.saturating_mul(1000)),
+                b"EXPIREAT" => amount.saturating_mul(1000),
+                _ => amount,
+            };
+            rewritten = if deadline <= unix_millis() {
+                vec![b"DEL".to_vec(), parts[1].clone()]
+            } else {
+                vec![
+                    b"PEXPIREAT".to_vec(),
+                    parts[1].clone(),
+                    deadline.to_string().into_bytes(),
+                ]
+            };
+            &rewritten
+        }
+        b"SETEX" => {
+            let seconds = parse_integer(&parts[2]).unwrap_or_default();
+            rewritten = vec![
+                b"SET".to_vec(),
+                parts[1].clone(),
+                parts[3].clone(),
+                b"PXAT".to_vec(),
+                deadline_after(seconds.saturating_mul(1000)),
+            ];
+            &rewritten
+        }
+        b"SET" => {
+            let mut set = parts.to_vec();
+            for index in 3..set.len() - 1 {
+                let unit = match set[index].to_ascii_uppercase().as_slice() {
+                    b"EX" => 1000,
+                    b"PX" => 1,
+                    _ => continue,
+                };
+                let amount = parse_integer(&set[index + 1]).unwrap_or_default();
+                set[index] = b"PXAT".to_vec();
+                set[index + 1] = deadline_after(amount.saturating_mul(unit));
+            }
+            rewritten = set;
+            &rewritten
+        }
+        b"RESTORE" => {
+            let ttl = parse_integer(&parts[2]).unwrap_or_default();
+            if ttl == 0
+                || parts[4..]
+                    .iter()
+                    .any(|option| option.eq_ignore_ascii_case(b"ABSTTL"))
+            {
+                parts
+            } else {
+                rewritten = [
+                    vec![parts[0].clone(), parts[1].clone(), deadline_after(ttl)],
+                    parts[3..].to_vec(),
+                    vec![b"ABSTTL".to_vec()],
+                ]
+                .concat();
+                &rewritten
+            }
+        }
+        _ => parts,
+    };
+
+    if let Err(e) = shared.aof.append(db, parts) {
+        tracing::error!(error = %e, "AOF write failed");
+    }
+}
+
+fn deadline_after(millis: i64) -> Vec<u8> {
+    unix_millis()
+        .saturating_add(millis)
+        .to_string()
+        .into_bytes()
+}
+
+fn wrong_context(command: &Command) -> RespMessage {
+    error(&format!(
+        "ERR Can't execute '{}' in this context",
+        command.name.to_lowercase()
+    ))
+}
+
+fn command_arity_error(name: &[u8]) -> RespMessage {
+    wrong_arity(&String::from_utf8_lossy(name).to_lowercase())
+}
+
+fn unknown_command(name: &[u8], args: &[Vec<u8>]) -> RespMessage {
+    let mut summary = String::new();
+    for arg in args {
+        if summary.len() >= UNKNOWN_COMMAND_ARGS_LEN {
+            break;
+        }
+        let arg = String::from_utf8_lossy(arg);
+        let arg: String = arg
+            .chars()
+            .take(UNKNOWN_COMMAND_ARGS_LEN - summary.len())
+            .collect();
+        summary.push_str(&format!("'{}' ", arg));
+    }
+
+    RespMessage::Error(format!(
+        "ERR unknown command '{}', with args beginning with: {}",
+        String::from_utf8_lossy(name),
+        summary
+    ))
+}
+
+fn ping(args: &[Vec<u8>]) -> RespMessage {
+    match args {
+        [] => RespMessage::SimpleString("PONG".to_string()),
+        [message] => RespMessage::BulkString(message.clone()),
+        _ => wrong_arity("ping"),
+    }
+}
+
+fn echo(args: &[Vec<u8>]) -> RespMessage {
+    match args {
+        [message] => RespMessage::BulkString(message.clone()),
+        _ => wrong_arity("echo"),
+    }
+}
+
+fn publish(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    let [channel, message] = args else {
+        return wrong_arity("publish");
+    };
+
+    RespMessage::Integer(shared.pubsub.publish(channel, message.clone()) as i64)
+}
+
+const PUBSUB_HELP: &[&str] = &[
+    "CHANNELS [<pattern>]",
+    "    Return the currently active channels matching a <pattern> (default: '*').",
+    "NUMSUB [<channel> ...]",
+    "    Return the number of subscribers for the specified channels.",
+];
+
+fn pubsub(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, args)) = args.split_first() else {
+        return wrong_arity("pubsub");
+    };
+
+    match (subcommand.to_ascii_uppercase().as_slice(), args) {
+        (b"CHANNELS", [] | [_]) => RespMessage::Array(
+            shared
+                .pubsub
+                .channels()
+                .into_iter()
+                .filter(|channel| {
+                    args.first()
+                        .is_none_or(|pattern| glob_match(pattern, channel))
+                })
+                .map(RespMessage::BulkString)
+                .collect(),
+        ),
+        (b"HELP", []) => help("PUBSUB", PUBSUB_HELP),
+        (b"NUMSUB", channels) => RespMessage::Array(
+            channels
+                .iter()
+                .flat_map(|channel| {
+                    [
+                        RespMessage::BulkString(channel.clone()),
+                        RespMessage::Integer(shared.pubsub.subscribers(channel) as i64),
+                    ]
+                })
+                .collect(),
+        ),
+        _ => error(&format!(
+            "ERR unknown subcommand or wrong number of arguments for '{}'. Try PUBSUB HELP.",
+            String::from_utf8_lossy(subcommand)
+        )),
+    }
+}
+
+fn help(command: &str, subcommands: &[&str]) -> RespMessage {
+    let usage = format!(
+        "{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+        command
+    );
+    let lines = std::iter::once(usage.as_str())
+        .chain(subcommands.iter().copied())
+        .chain(["HELP", "    Print this help."]);
+
+    RespMessage::Array(
+        lines
+            .map(|line| RespMessage::SimpleString(line.to_string()))
+            .collect(),
+    )
+}
+
+fn unknown_subcommand(command: &str, subcommand: &[u8]) -> RespMessage {
+    error(&format!(
+        "ERR unknown subcommand '{}'. Try {} HELP.",
+        String::from_utf8_lossy(subcommand),
+        command
+    ))
+}
+
+pub(crate) fn wrong_arity(name: &str) -> RespMessage {
+    RespMessage::Error(format!(
+        "ERR wrong number of arguments for '{}' command",
+        name
+    ))
+}
+
+fn error(message: &str) -> RespMessage {
+    RespMessage::Error(message.to_string())
+}
+
+fn ok() -> RespMessage {
+    RespMessage::SimpleString("OK".to_string())
+}
+
+fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    Some((start as usize, stop as usize))
+}
+
+fn parse_integer(arg: &[u8]) -> Option<i64> {
+    std::str::from_utf8(arg).ok()?.parse().ok()
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn ttl_millis(amount: i64, unit: Duration) -> Option<i64> {
+    let millis = amount.checked_mul(unit.as_millis() as i64)?;
+    unix_millis().checked_add(millis).map(|_| millis)
+}
+
+fn parse_float(arg: &[u8]) -> Option<f64> {
+    let value: f64 = std::str::from_utf8(arg).ok()?.parse().ok()?;
+    (!value.is_nan()).then_some(value)
+}
+
+#[cfg(test)]
+pub(crate) fn command(parts: &[&str]) -> RespMessage {
+    RespMessage::Array(
+        parts
+            .iter()
+            .map(|part| RespMessage::BulkString(part.as_bytes().to_vec()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+fn run(shared: &Shared, cmd: RespMessage) -> RespMessage {
+    dispatch(shared, &mut Client::default(), cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    #[test]
+    fn test_ping() {
+        assert_eq!(
+            run(&Shared::default(), command(&["PING"])),
+            RespMessage::SimpleString("PONG".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ping_with_message() {
+        assert_eq!(
+            run(&Shared::default(), command(&["PING", "hello"])),
+            RespMessage::BulkString(b"hello".to_vec())
+        );
+        assert_eq!(
+            run(&Shared::default(), command(&["PING", "a", "b"])),
+            RespMessage::Error("ERR wrong number of arguments for 'ping' command".to_string())
+        );
+    }
+
+    #[test]
+    fn test_command_names_are_case_insensitive() {
+        let shared = Shared::default();
+        run(&shared, command(&["set", "foo", "bar"]));
+
+        for name in ["get", "GET", "Get"] {
+            assert_eq!(
+                run(&shared, command(&[name, "foo"])),
+                RespMessage::BulkString(b"bar".to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrong_number_of_arguments() {
+        assert_eq!(
+            run(&Shared::default(), command(&["GET"])),
+            RespMessage::Error("ERR wrong number of arguments for 'get' command".to_string())
+        );
+        assert_eq!(
+            run(&Shared::default(), command(&["set", "foo"])),
+            RespMessage::Error("ERR wrong number of arguments for 'set' command".to_string())
+        );
+    }
+
+    #[test]
+    fn test_multi_key_commands_span_shards() {
+        let shared = Shared::default();
+        let keys: Vec<String> = (0..32).map(|i| format!("key:{}", i)).collect();
+        for key in &keys {
+            run(&shared, command(&["SET", key, "x"]));
+        }
+
+        let mut del = vec!["DEL"];
+        del.extend(keys.iter().map(String::as_str));
+        assert_eq!(run(&shared, command(&del)), RespMessage::Integer(32));
+        assert_eq!(run(&shared, command(&["DBSIZE"])), RespMessage::Integer(0));
+    }
+
+    #[test]
+    fn test_echo() {
+        assert_eq!(
+            run(&Shared::default(), command(&["ECHO", "hello"])),
+            RespMessage::BulkString(b"hello".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        assert_eq!(
+            run(&Shared::default(), command(&["FOOBAR", "1", "2"])),
+            RespMessage::Error(
+                "ERR unknown command 'FOOBAR', with args beginning with: '1' '2' ".to_string()
+            )
+        );
+        assert_eq!(
+            run(&Shared::default(), command(&["FOOBAR"])),
+            RespMessage::Error(
+                "ERR unknown command 'FOOBAR', with args beginning with: ".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_truncates_args() {
+        let long = "x".repeat(200);
+        let RespMessage::Error(message) =
+            run(&Shared::default(), command(&["FOOBAR", &long, "next"]))
+        else {
+            panic!("expected an error");
+        };
+
+        assert!(message.ends_with(&format!("'{}' ", "x".repeat(128))));
+    }
+
+    #[test]
+    fn test_non_bulk_string_argument() {
+        assert!(matches!(
+            run(
+                &Shared::default(),
+                RespMessage::Array(vec![
+                    RespMessage::BulkString(b"GET".to_vec()),
+                    RespMessage::Integer(1)
+                ])
+            ),
+            RespMessage::Error(message) if message.starts_with("ERR Protocol error")
+        ));
+    }
+
+    #[test]
+    fn test_cross_type_operations_return_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "string", "1"]));
+        run(&shared, command(&["RPUSH", "list", "a"]));
+        run(&shared, command(&["HSET", "hash", "a", "1"]));
+
+        let wrong_type = RespMessage::Error(
+            "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+        );
+        for cmd in [
+            &["GET", "list"][..],
+            &["INCR", "hash"],
+            &["LRANGE", "string", "0", "-1"],
+            &["RPUSH", "hash", "a"],
+            &["HGET", "list", "a"],
+            &["HSET", "string", "a", "1"],
+            &["HGETALL", "list"],
+        ] {
+            assert_eq!(run(&shared, command(cmd)), wrong_type, "{:?}", cmd);
+        }
+    }
+
+    #[test]
+    fn test_set_overwrites_any_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "key", "a"]));
+
+        assert_eq!(
+            run(&shared, command(&["SET", "key", "value"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "key"])),
+            RespMessage::BulkString(b"value".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_pubsub_channels() {
+        let shared = Shared::default();
+        let _news = shared.pubsub.subscribe(b"news.tech");
+        let _sports = shared.pubsub.subscribe(b"sports");
+
+        assert_eq!(
+            run(&shared, command(&["PUBSUB", "CHANNELS"])),
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"news.tech".to_vec()),
+                RespMessage::BulkString(b"sports".to_vec()),
+            ])
+        );
+        assert_eq!(
+            run(&shared, command(&["PUBSUB", "CHANNELS", "news.*"])),
+            RespMessage::Array(vec![RespMessage::BulkString(b"news.tech".to_vec())])
+        );
+    }
+
+    #[test]
+    fn test_pubsub_numsub() {
+        let shared = Shared::default();
+        let _first = shared.pubsub.subscribe(b"news");
+        let _second = shared.pubsub.subscribe(b"news");
+
+        assert_eq!(
+            run(&shared, command(&["PUBSUB", "NUMSUB", "news", "sports"])),
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"news".to_vec()),
+                RespMessage::Integer(2),
+                RespMessage::BulkString(b"sports".to_vec()),
+                RespMessage::Integer(0),
+            ])
+        );
+    }
+
+    fn limit_memory(shared: &Shared, maxmemory: usize, policy: &str) {
+        run(
+            shared,
+            command(&["CONFIG", "SET", "maxmemory", &maxmemory.to_string()]),
+        );
+        run(
+            shared,
+            command(&["CONFIG", "SET", "maxmemory-policy", policy]),
+        );
+    }
+
+    #[test]
+    fn test_noeviction_rejects_writes_over_limit() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "a", &"x".repeat(200)]));
+        limit_memory(&shared, 100, "noeviction");
+
+        assert_eq!(
+            run(&shared, command(&["SET", "b", "1"])),
+            RespMessage::Error(
+                "OOM command not allowed when used memory > 'maxmemory'.".to_string()
+            )
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "a"])),
+            RespMessage::BulkString("x".repeat(200).into_bytes())
+        );
+        assert_eq!(
+            run(&shared, command(&["DEL", "a"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["SET", "b", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+    }
+
+    #[test]
+    fn test_allkeys_random_makes_room() {
+        let shared = Shared::default();
+        for i in 0..10 {
+            run(
+                &shared,
+                command(&["SET", &format!("key:{}", i), &"x".repeat(100)]),
+            );
+        }
+        let limit = shared.store.used_memory() / 2;
+        limit_memory(&shared, limit, "allkeys-random");
+
+        assert_eq!(
+            run(&shared, command(&["SET", "new", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert!(shared.store.lock(0).len() < 10);
+        assert!(
+            shared
+                .stats
+                .evicted_keys
+                .load(std::sync::atomic::Ordering::Relaxed)
+                > 0
+        );
+    }
+
+    #[test]
+    fn test_volatile_ttl_only_evicts_keys_with_ttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "persistent", &"x".repeat(200)]));
+        run(&shared, command(&["SET", "volatile", &"x".repeat(200)]));
+        run(&shared, command(&["EXPIRE", "volatile", "100"]));
+        limit_memory(&shared, 400, "volatile-ttl");
+
+        assert_eq!(
+            run(&shared, command(&["SET", "new", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "persistent", "volatile"])),
+            RespMessage::Integer(1)
+        );
+
+        limit_memory(&shared, 100, "volatile-ttl");
+        assert!(matches!(
+            run(&shared, command(&["SET", "new", "2"])),
+            RespMessage::Error(message) if message.starts_with("OOM ")
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    commands::{
+        NOT_AN_INTEGER, REDIS_VERSION, SYNTAX_ERROR, error, help, ok, parse_integer,
+        unknown_subcommand, wrong_arity,
+    },
+    connection::Client,
+    parser::{RespMessage, RespVersion},
+    server::Shared,
+};
+
+pub fn auth(shared: &Shared, client: &mut Client, args: &[Vec<u8>]) -> RespMessage {
+    let password = match args {
+        [password] => password,
+        [username, password] if username == b"default" => password,
+        [_, _] => return error("WRONGPASS invalid username-password pair or user is disabled."),
+        _ => return wrong_arity("auth"),
+    };
+    let config = shared.config();
+    let Some(requirepass) = &config.requirepass else {
+        return error(
+            "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?",
+        );
+    };
+
+    if !constant_time_eq(password, requirepass.as_bytes()) {
+        return error("ERR invalid password");
+    }
+
+    client.authenticated = true;
+    ok()
+}
+
+pub fn hello(shared: &Shared, client: &mut Client, args: &[Vec<u8>]) -> RespMessage {
+    let (protocol, mut options) = match args.split_first() {
+        Some((version, options)) => {
+            let protocol = match parse_integer(version) {
+                Some(2) => RespVersion::Resp2,
+                Some(3) => RespVersion::Resp3,
+                Some(_) => return error("NOPROTO unsupported protocol version"),
+                None => return error("ERR Protocol version is not an integer or out of range"),
+            };
+            (protocol, options)
+        }
+        None => (client.protocol, args),
+    };
+
+    let mut credentials = None;
+    while let Some((option, rest)) = options.split_first() {
+        match (option.to_ascii_uppercase().as_slice(), rest) {
+            (b"AUTH", [username, password, rest @ ..]) => {
+                credentials = Some([username.clone(), password.clone()]);
+                options = rest;
+            }
+            _ => return error(SYNTAX_ERROR),
+        }
+    }
+
+    match credentials {
+        Some(credentials) => {
+            if let RespMessage::Error(e) = auth(shared, client, &credentials) {
+                return RespMessage::Error(e);
+            }
+        }
+        None if !client.is_authenticated(&shared.config()) => {
+            return error(
+                "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time",
+            );
+        }
+        None => {}
+    }
+
+    client.protocol = protocol;
+    let proto = match protocol {
+        RespVersion::Resp2 => 2,
+        RespVersion::Resp3 => 3,
+    };
+    let field = |name: &str| RespMessage::BulkString(name.as_bytes().to_vec());
+    RespMessage::Map(vec![
+        (field("server"), field("redis")),
+        (field("version"), field(REDIS_VERSION)),
+        (field("proto"), RespMessage::Integer(proto)),
+        (field("id"), RespMessage::Integer(client.id as i64)),
+        (field("mode"), field("standalone")),
+        (field("role"), field("master")),
+        (field("modules"), RespMessage::Array(vec![])),
+    ])
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+pub fn select(shared: &Shared, client: &mut Client, args: &[Vec<u8>]) -> RespMessage {
+    let [index] = args else {
+        return wrong_arity("select");
+    };
+    let Some(index) = parse_integer(index) else {
+        return error(NOT_AN_INTEGER);
+    };
+    if index < 0 || index as usize >= shared.store.databases() {
+        return error("ERR DB index is out of range");
+    }
+
+    client.db = index as usize;
+    shared.clients.set_db(client.id, client.db);
+    ok()
+}
+
+pub fn reset(shared: &Shared, client: &mut Client) -> RespMessage {
+    *client = Client {
+        id: client.id,
+        addr: std::mem::take(&mut client.addr),
+        name: client.name.take(),
+        ..Client::default()
+    };
+    shared.clients.set_db(client.id, client.db);
+    RespMessage::SimpleString("RESET".to_string())
+}
+
+const CLIENT_HELP: &[&str] = &[
+    "GETNAME",
+    "    Return the name of the current connection.",
+    "ID",
+    "    Return the ID of the current connection.",
+    "LIST",
+    "    Return information about client connections.",
+    "SETNAME <name>",
+    "    Assign the name <name> to the current connection.",
+];
+
+pub fn client(shared: &Shared, client: &mut Client, args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, args)) = args.split_first() else {
+        return wrong_arity("client");
+    };
+
+    match (subcommand.to_ascii_uppercase().as_slice(), args) {
+        (b"SETNAME", [name]) => {
+            if name.iter().any(|byte| !(b'!'..=b'~').contains(byte)) {
+                return error(
+                    "ERR Client names cannot contain spaces, newlines or special characters.",
+                );
+            }
+            client.name = Some(name.clone()).filter(|name| !name.is_empty());
+            shared.clients.set_name(client.id, client.name.clone());
+            ok()
+        }
+        (b"GETNAME", []) => RespMessage::BulkString(client.name.clone().unwrap_or_default()),
+        (b"ID", []) => RespMessage::Integer(client.id as i64),
+        (b"LIST", []) => RespMessage::BulkString(shared.clients.list().into_bytes()),
+        (b"HELP", []) => help("CLIENT", CLIENT_HELP),
+        (b"SETNAME" | b"GETNAME" | b"ID" | b"LIST", _) => wrong_arity(&format!(
+            "client|{}",
+            String::from_utf8_lossy(subcommand).to_lowercase()
+        )),
+        _ => unknown_subcommand("CLIENT", subcommand),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use crate::commands::{command, dispatch};
+    use crate::config::Config;
+    use crate::connection::Client;
+    use crate::parser::{RespMessage, RespVersion};
+    use crate::server::Shared;
+
+    fn shared_with_password(password: &str) -> Shared {
+        Shared {
+            config: Arc::new(RwLock::new(Config {
+                requirepass: Some(password.to_string()),
+                ..Config::default()
+            })),
+            ..Shared::default()
+        }
+    }
+
+    #[test]
+    fn test_select_isolates_databases() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["SET", "foo", "0"]));
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["SELECT", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::Null
+        );
+
+        dispatch(&shared, &mut client, command(&["SET", "foo", "1"]));
+        dispatch(&shared, &mut client, command(&["SELECT", "0"]));
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"0".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_select_out_of_range() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        for index in ["16", "-1"] {
+            assert_eq!(
+                dispatch(&shared, &mut client, command(&["SELECT", index])),
+                RespMessage::Error("ERR DB index is out of range".to_string())
+            );
+        }
+        assert_eq!(client.db, 0);
+    }
+
+    #[test]
+    fn test_commands_require_auth() {
+        let shared = shared_with_password("secret");
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::Error("NOAUTH Authentication required.".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["PING"])),
+            RespMessage::SimpleString("PONG".to_string())
+        );
+    }
+
+    #[test]
+    fn test_auth_with_correct_password() {
+        let shared = shared_with_password("secret");
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["AUTH", "secret"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_auth_with_wrong_password() {
+        let shared = shared_with_password("secret");
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["AUTH", "nope"])),
+            RespMessage::Error("ERR invalid password".to_string())
+        );
+        assert!(!client.authenticated);
+    }
+
+    #[test]
+    fn test_auth_without_password_configured() {
+        assert!(matches!(
+            dispatch(
+                &Shared::default(),
+                &mut Client::default(),
+                command(&["AUTH", "secret"])
+            ),
+            RespMessage::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_hello_switches_protocol() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        let RespMessage::Map(fields) = dispatch(&shared, &mut client, command(&["HELLO", "3"]))
+        else {
+            panic!("expected a map");
+        };
+
+        assert!(fields.contains(&(
+            RespMessage::BulkString(b"proto".to_vec()),
+            RespMessage::Integer(3)
+        )));
+        assert_eq!(client.protocol, RespVersion::Resp3);
+    }
+
+    #[test]
+    fn test_hello_unsupported_protocol() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["HELLO", "4"])),
+            RespMessage::Error("NOPROTO unsupported protocol version".to_string())
+        );
+        assert_eq!(client.protocol, RespVersion::Resp2);
+    }
+
+    #[test]
+    fn test_hello_authenticates() {
+        let shared = shared_with_password("secret");
+        let mut client = Client::default();
+
+        assert!(matches!(
+            dispatch(&shared, &mut client, command(&["HELLO", "3"])),
+            RespMessage::Error(message) if message.starts_with("NOAUTH")
+        ));
+        assert!(matches!(
+            dispatch(
+                &shared,
+                &mut client,
+                command(&["HELLO", "3", "AUTH", "default", "secret"])
+            ),
+            RespMessage::Map(_)
+        ));
+        assert!(client.authenticated);
+    }
+
+    #[test]
+    fn test_client_setname_getname() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["CLIENT", "GETNAME"])),
+            RespMessage::BulkString(Vec::new())
+        );
+        assert_eq!(
+            dispatch(
+                &shared,
+                &mut client,
+                command(&["CLIENT", "SETNAME", "worker-1"])
+            ),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["CLIENT", "GETNAME"])),
+            RespMessage::BulkString(b"worker-1".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_client_setname_rejects_invalid_name() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        for name in ["has space", "has\nnewline"] {
+            assert!(matches!(
+                dispatch(&shared, &mut client, command(&["CLIENT", "SETNAME", name])),
+                RespMessage::Error(_)
+            ));
+        }
+        assert_eq!(client.name, None);
+    }
+
+    #[test]
+    fn test_client_id() {
+        let shared = Shared::default();
+        let mut client = Client::new(42);
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["CLIENT", "ID"])),
+            RespMessage::Integer(42)
+        );
+    }
+
+    #[test]
+    fn test_reset_aborts_transaction() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["SELECT", "1"]));
+        dispatch(&shared, &mut client, command(&["WATCH", "foo"]));
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "queued"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["RESET"])),
+            RespMessage::SimpleString("RESET".to_string())
+        );
+        assert!(client.transaction.is_none());
+        assert!(client.watched.is_empty());
+        assert_eq!(client.db, 0);
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["SET", "foo", "bar"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Error("ERR EXEC without MULTI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reset_deauthenticates() {
+        let shared = shared_with_password("secret");
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["AUTH", "secret"]));
+        dispatch(&shared, &mut client, command(&["HELLO", "3"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["RESET"])),
+            RespMessage::SimpleString("RESET".to_string())
+        );
+        assert_eq!(client.protocol, RespVersion::Resp2);
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET", "foo"])),
+            RespMessage::Error("NOAUTH Authentication required.".to_string())
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+
+use crate::{
+    commands::{
+        NOT_A_FLOAT, NOT_AN_INTEGER, WRONG_TYPE, error, parse_float, parse_integer, wrong_arity,
+    },
+    parser::RespMessage,
+    store::{Keyspace, Value},
+};
+
+pub fn hset(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, pairs @ ..] = args else {
+        return wrong_arity("hset");
+    };
+    if pairs.is_empty() || pairs.len() % 2 != 0 {
+        return wrong_arity("hset");
+    }
+
+    let Value::Hash(hash) = keyspace.get_or_insert_with(key, || Value::Hash(HashMap::new())) else {
+        return error(WRONG_TYPE);
+    };
+
+    let mut added = 0;
+    for pair in pairs.chunks(2) {
+        if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+            added += 1;
+        }
+    }
+
+    RespMessage::Integer(added)
+}
+
+pub fn hget(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, field] = args else {
+        return wrong_arity("hget");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => match hash.get(field) {
+            Some(value) => RespMessage::BulkString(value.clone()),
+            None => RespMessage::Null,
+        },
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn hgetall(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("hgetall");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => RespMessage::Map(
+            hash.iter()
+                .map(|(field, value)| {
+                    (
+                        RespMessage::BulkString(field.clone()),
+                        RespMessage::BulkString(value.clone()),
+                    )
+                })
+                .collect(),
+        ),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Map(vec![]),
+    }
+}
+
+pub fn hdel(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, fields @ ..] = args else {
+        return wrong_arity("hdel");
+    };
+    let (removed, now_empty) = match keyspace.get_mut(key) {
+        Some(Value::Hash(hash)) => {
+            let removed = fields
+                .iter()
+                .filter(|field| hash.remove(*field).is_some())
+                .count();
+            (removed, hash.is_empty())
+        }
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Integer(0),
+    };
+    if now_empty {
+        keyspace.remove(key);
+    }
+
+    RespMessage::Integer(removed as i64)
+}
+
+pub fn hexists(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, field] = args else {
+        return wrong_arity("hexists");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => RespMessage::Integer(hash.contains_key(field) as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn hlen(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("hlen");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => RespMessage::Integer(hash.len() as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn hkeys(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("hkeys");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => {
+            RespMessage::Array(hash.keys().cloned().map(RespMessage::BulkString).collect())
+        }
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Array(vec![]),
+    }
+}
+
+pub fn hvals(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("hvals");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Hash(hash)) => RespMessage::Array(
+            hash.values()
+                .cloned()
+                .map(RespMessage::BulkString)
+                .collect(),
+        ),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Array(vec![]),
+    }
+}
+
+pub fn hincrby(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, field, delta] = args else {
+        return wrong_arity("hincrby");
+    };
+    let Some(delta) = parse_integer(delta) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let Value::Hash(hash) = keyspace.get_or_insert_with(key, || Value::Hash(HashMap::new())) else {
+        return error(WRONG_TYPE);
+    };
+    let current = match hash.get(field) {
+        Some(value) => match parse_integer(value) {
+            Some(current) => current,
+            None => return error("ERR hash value is not an integer"),
+        },
+        None => 0,
+    };
+    let Some(updated) = current.checked_add(delta) else {
+        return error("ERR increment or decrement would overflow");
+    };
+
+    hash.insert(field.clone(), updated.to_string().into_bytes());
+    RespMessage::Integer(updated)
+}
+
+pub fn hincrbyfloat(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, field, delta] = args else {
+        return wrong_arity("hincrbyfloat");
+    };
+    let Some(delta) = parse_float(delta) else {
+        return error(NOT_A_FLOAT);
+    };
+
+    let Value::Hash(hash) = keyspace.get_or_insert_with(key, || Value::Hash(HashMap::new())) else {
+        return error(WRONG_TYPE);
+    };
+    let current = match hash.get(field) {
+        Some(value) => match parse_float(value) {
+            Some(current) => current,
+            None => return error("ERR hash value is not a float"),
+        },
+        None => 0.0,
+    };
+    let updated = current + delta;
+    if !updated.is_finite() {
+        if hash.is_empty() {
+            keyspace.remove(key);
+        }
+        return error("ERR increment would produce NaN or Infinity");
+    }
+
+    let encoded = updated.to_string().into_bytes();
+    hash.insert(field.clone(), encoded.clone());
+    RespMessage::BulkString(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    #[test]
+    fn test_hset_then_hget() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["HSET", "hash", "a", "1", "b", "2"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["HGET", "hash", "a"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["HGET", "hash", "missing"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_hset_overwrite_existing_field() {
+        let shared = Shared::default();
+        run(&shared, command(&["HSET", "hash", "a", "1"]));
+
+        assert_eq!(
+            run(&shared, command(&["HSET", "hash", "a", "2", "b", "3"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["HGET", "hash", "a"])),
+            RespMessage::BulkString(b"2".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_hgetall_pairs_fields_with_values() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["HSET", "hash", "a", "1", "b", "2", "c", "3"]),
+        );
+
+        let RespMessage::Map(mut pairs) = run(&shared, command(&["HGETALL", "hash"])) else {
+            panic!("expected a map");
+        };
+        pairs.sort_by_key(|(field, _)| format!("{:?}", field));
+
+        assert_eq!(
+            pairs,
+            ["a", "b", "c"]
+                .iter()
+                .zip(["1", "2", "3"])
+                .map(|(field, value)| (
+                    RespMessage::BulkString(field.as_bytes().to_vec()),
+                    RespMessage::BulkString(value.as_bytes().to_vec())
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn sorted(reply: RespMessage) -> Vec<RespMessage> {
+        let RespMessage::Array(mut items) = reply else {
+            panic!("expected an array");
+        };
+        items.sort_by_key(|item| format!("{:?}", item));
+        items
+    }
+
+    #[test]
+    fn test_hdel_multiple_fields() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["HSET", "hash", "a", "1", "b", "2", "c", "3"]),
+        );
+
+        assert_eq!(
+            run(&shared, command(&["HDEL", "hash", "a", "b", "missing"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["HLEN", "hash"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["HEXISTS", "hash", "a"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["HEXISTS", "hash", "c"])),
+            RespMessage::Integer(1)
+        );
+    }
+
+    #[test]
+    fn test_hdel_last_field_removes_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["HSET", "hash", "a", "1"]));
+
+        assert_eq!(
+            run(&shared, command(&["HDEL", "hash", "a"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "hash"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["HLEN", "hash"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_hkeys_and_hvals() {
+        let shared = Shared::default();
+        run(&shared, command(&["HSET", "hash", "a", "1", "b", "2"]));
+
+        assert_eq!(
+            sorted(run(&shared, command(&["HKEYS", "hash"]))),
+            vec![
+                RespMessage::BulkString(b"a".to_vec()),
+                RespMessage::BulkString(b"b".to_vec()),
+            ]
+        );
+        assert_eq!(
+            sorted(run(&shared, command(&["HVALS", "hash"]))),
+            vec![
+                RespMessage::BulkString(b"1".to_vec()),
+                RespMessage::BulkString(b"2".to_vec()),
+            ]
+        );
+        assert_eq!(
+            run(&shared, command(&["HKEYS", "missing"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_hincrby_missing_field() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["HINCRBY", "hash", "count", "5"])),
+            RespMessage::Integer(5)
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBY", "hash", "count", "-2"])),
+            RespMessage::Integer(3)
+        );
+        assert_eq!(
+            run(&shared, command(&["HGET", "hash", "count"])),
+            RespMessage::BulkString(b"3".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["TYPE", "hash"])),
+            RespMessage::SimpleString("hash".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hincrby_errors() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["HSET", "hash", "name", "bob", "max", &i64::MAX.to_string()]),
+        );
+
+        assert_eq!(
+            run(&shared, command(&["HINCRBY", "hash", "name", "1"])),
+            RespMessage::Error("ERR hash value is not an integer".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBY", "hash", "max", "1"])),
+            RespMessage::Error("ERR increment or decrement would overflow".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBY", "hash", "count", "one"])),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hincrbyfloat_missing_field() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "hash", "price", "10.5"])),
+            RespMessage::BulkString(b"10.5".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "hash", "price", "-0.5"])),
+            RespMessage::BulkString(b"10".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_hincrbyfloat_errors() {
+        let shared = Shared::default();
+        run(&shared, command(&["HSET", "hash", "name", "bob"]));
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "hash", "name", "1"])),
+            RespMessage::Error("ERR hash value is not a float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "hash", "price", "abc"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "new", "price", "inf"])),
+            RespMessage::Error("ERR increment would produce NaN or Infinity".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "new"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["HINCRBYFLOAT", "foo", "price", "1"])),
+            RespMessage::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_hash_commands_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        for cmd in [
+            &["HDEL", "foo", "a"][..],
+            &["HEXISTS", "foo", "a"],
+            &["HLEN", "foo"],
+            &["HKEYS", "foo"],
+            &["HVALS", "foo"],
+        ] {
+            assert_eq!(
+                run(&shared, command(cmd)),
+                RespMessage::Error(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_hget_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["HGET", "foo", "a"])),
+            RespMessage::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,1400 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    commands::{
+        NOT_AN_INTEGER, SYNTAX_ERROR, error, help, ok, parse_integer, ttl_millis, unix_millis,
+        wrong_arity,
+    },
+    connection::Client,
+    glob::glob_match,
+    parser::RespMessage,
+    rdb,
+    server::Shared,
+    store::{Keyspace, Value},
+};
+
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+const EMBSTR_MAX_LEN: usize = 44;
+const LISTPACK_MAX_ENTRIES: usize = 128;
+const LISTPACK_MAX_VALUE: usize = 64;
+const INTSET_MAX_ENTRIES: usize = 512;
+
+pub fn del(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let deleted = args.iter().filter(|key| keyspace.remove(key)).count();
+    RespMessage::Integer(deleted as i64)
+}
+
+pub fn exists(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let count = args.iter().filter(|key| keyspace.exists(key)).count();
+    RespMessage::Integer(count as i64)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExpireCondition {
+    Always,
+    IfNoTtl,
+    IfTtl,
+    IfGreater,
+    IfLess,
+}
+
+fn parse_expire_condition(options: &[Vec<u8>]) -> Result<ExpireCondition, RespMessage> {
+    let mut condition = ExpireCondition::Always;
+    for option in options {
+        let parsed = match option.to_ascii_uppercase().as_slice() {
+            b"NX" => ExpireCondition::IfNoTtl,
+            b"XX" => ExpireCondition::IfTtl,
+            b"GT" => ExpireCondition::IfGreater,
+            b"LT" => ExpireCondition::IfLess,
+            _ => {
+                return Err(error(&format!(
+                    "ERR Unsupported option {}",
+                    String::from_utf8_lossy(option)
+                )));
+            }
+        };
+        if condition != ExpireCondition::Always && condition != parsed {
+            return Err(error(SYNTAX_ERROR));
+        }
+        condition = parsed;
+    }
+
+    Ok(condition)
+}
+
+pub fn expire(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, seconds, options @ ..] = args else {
+        return wrong_arity("expire");
+    };
+    let Some(seconds) = parse_integer(seconds) else {
+        return error(NOT_AN_INTEGER);
+    };
+    let condition = match parse_expire_condition(options) {
+        Ok(condition) => condition,
+        Err(reply) => return reply,
+    };
+
+    let Some(millis) = ttl_millis(seconds, Duration::from_secs(1)) else {
+        return error("ERR invalid expire time in 'expire' command");
+    };
+
+    let ttl = (millis > 0).then(|| Duration::from_millis(millis as u64));
+    match expire_after(keyspace, key, ttl, condition) {
+        Some(applied) => RespMessage::Integer(applied as i64),
+        None => error("ERR invalid expire time in 'expire' command"),
+    }
+}
+
+pub fn expireat(
+    keyspace: &mut Keyspace,
+    args: &[Vec<u8>],
+    unit: Duration,
+    name: &str,
+) -> RespMessage {
+    let [key, timestamp, options @ ..] = args else {
+        return wrong_arity(name);
+    };
+    let Some(timestamp) = parse_integer(timestamp) else {
+        return error(NOT_AN_INTEGER);
+    };
+    let condition = match parse_expire_condition(options) {
+        Ok(condition) => condition,
+        Err(reply) => return reply,
+    };
+    let Some(deadline) = timestamp
+        .checked_mul(unit.as_millis() as i64)
+        .and_then(|millis| UNIX_EPOCH.checked_add(Duration::from_millis(millis.max(0) as u64)))
+    else {
+        return error(&format!("ERR invalid expire time in '{}' command", name));
+    };
+
+    let ttl = deadline.duration_since(SystemTime::now()).ok();
+    match expire_after(keyspace, key, ttl, condition) {
+        Some(applied) => RespMessage::Integer(applied as i64),
+        None => error(&format!("ERR invalid expire time in '{}' command", name)),
+    }
+}
+
+fn expire_after(
+    keyspace: &mut Keyspace,
+    key: &[u8],
+    ttl: Option<Duration>,
+    condition: ExpireCondition,
+) -> Option<bool> {
+    let now = Instant::now();
+    let deadline = match ttl {
+        Some(ttl) => Some(now.checked_add(ttl)?),
+        None => None,
+    };
+    let Some(current) = keyspace.ttl(key) else {
+        return Some(false);
+    };
+    let current = current.map(|current| now + current);
+    let requested = deadline.unwrap_or(now);
+    let allowed = match condition {
+        ExpireCondition::Always => true,
+        ExpireCondition::IfNoTtl => current.is_none(),
+        ExpireCondition::IfTtl => current.is_some(),
+        ExpireCondition::IfGreater => current.is_some_and(|current| requested > current),
+        ExpireCondition::IfLess => current.is_none_or(|current| requested < current),
+    };
+    if !allowed {
+        return Some(false);
+    }
+
+    Some(match ttl {
+        Some(ttl) if !ttl.is_zero() => keyspace.expire(key, ttl),
+        _ => keyspace.remove(key),
+    })
+}
+
+pub fn persist(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("persist");
+    };
+
+    RespMessage::Integer(keyspace.persist(key) as i64)
+}
+
+pub fn ttl(keyspace: &mut Keyspace, args: &[Vec<u8>], unit: Duration, name: &str) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity(name);
+    };
+
+    match keyspace.ttl(key) {
+        None => RespMessage::Integer(-2),
+        Some(None) => RespMessage::Integer(-1),
+        Some(Some(remaining)) => {
+            let unit = unit.as_millis();
+            RespMessage::Integer(((remaining.as_millis() + unit / 2) / unit) as i64)
+        }
+    }
+}
+
+pub fn keys(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [pattern] = args else {
+        return wrong_arity("keys");
+    };
+
+    RespMessage::Array(
+        keyspace
+            .keys()
+            .into_iter()
+            .filter(|key| glob_match(pattern, key))
+            .map(|key| RespMessage::BulkString(key.clone()))
+            .collect(),
+    )
+}
+
+pub fn type_of(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("type");
+    };
+
+    let name = keyspace.get(key).map_or("none", |value| value.type_name());
+    RespMessage::SimpleString(name.to_string())
+}
+
+const OBJECT_HELP: &[&str] = &[
+    "ENCODING <key>",
+    "    Return the kind of internal representation used in order to store the value",
+    "    associated with a <key>.",
+    "IDLETIME <key>",
+    "    Return the idle time of the <key>, that is the approximated number of",
+    "    seconds elapsed since the last access to the key.",
+    "REFCOUNT <key>",
+    "    Return the number of references of the value associated with the specified",
+    "    <key>.",
+];
+
+pub fn object(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, args)) = args.split_first() else {
+        return wrong_arity("object");
+    };
+
+    match (subcommand.to_ascii_uppercase().as_slice(), args) {
+        (b"ENCODING", [key]) => match keyspace.get(key) {
+            Some(value) => RespMessage::SimpleString(encoding(value).to_string()),
+            None => error("ERR no such key"),
+        },
+        (b"REFCOUNT", [key]) => match keyspace.idle_time(key) {
+            Some(_) => RespMessage::Integer(1),
+            None => error("ERR no such key"),
+        },
+        (b"IDLETIME", [key]) => match keyspace.idle_time(key) {
+            Some(idle) => RespMessage::Integer(idle.as_secs() as i64),
+            None => error("ERR no such key"),
+        },
+        (b"HELP", []) => help("OBJECT", OBJECT_HELP),
+        _ => error(&format!(
+            "ERR unknown subcommand or wrong number of arguments for '{}'. Try OBJECT HELP.",
+            String::from_utf8_lossy(subcommand)
+        )),
+    }
+}
+
+fn encoding(value: &Value) -> &'static str {
+    match value {
+        Value::String(bytes) if parse_integer(bytes).is_some() => "int",
+        Value::String(bytes) if bytes.len() <= EMBSTR_MAX_LEN => "embstr",
+        Value::String(_) => "raw",
+        Value::List(list) if is_compact(list.len(), list.iter()) => "listpack",
+        Value::List(_) => "quicklist",
+        Value::Hash(hash) if is_compact(hash.len(), hash.iter().flat_map(|(f, v)| [f, v])) => {
+            "listpack"
+        }
+        Value::Set(set)
+            if set.len() <= INTSET_MAX_ENTRIES
+                && set.iter().all(|member| parse_integer(member).is_some()) =>
+        {
+            "intset"
+        }
+        Value::Set(set) if is_compact(set.len(), set.iter()) => "listpack",
+        Value::SortedSet(set) if is_compact(set.len(), set.iter().map(|(member, _)| member)) => {
+            "listpack"
+        }
+        Value::SortedSet(_) => "skiplist",
+        Value::Hash(_) | Value::Set(_) => "hashtable",
+    }
+}
+
+fn is_compact<'a>(len: usize, mut elements: impl Iterator<Item = &'a Vec<u8>>) -> bool {
+    len <= LISTPACK_MAX_ENTRIES && elements.all(|element| element.len() <= LISTPACK_MAX_VALUE)
+}
+
+pub fn dump(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("dump");
+    };
+
+    match keyspace.get(key) {
+        Some(value) => RespMessage::BulkString(rdb::dump(value)),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn restore(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, ttl, payload, options @ ..] = args else {
+        return wrong_arity("restore");
+    };
+    let Some(ttl) = parse_integer(ttl) else {
+        return error(NOT_AN_INTEGER);
+    };
+    if ttl < 0 {
+        return error("ERR Invalid TTL value, must be >= 0");
+    }
+
+    let mut replace = false;
+    let mut absolute = false;
+    for option in options {
+        match option.to_ascii_uppercase().as_slice() {
+            b"REPLACE" => replace = true,
+            b"ABSTTL" => absolute = true,
+            _ => return error(SYNTAX_ERROR),
+        }
+    }
+    if !replace && keyspace.exists(key) {
+        return error("BUSYKEY Target key name already exists.");
+    }
+    let Ok(value) = rdb::restore(payload) else {
+        return error("ERR DUMP payload version or checksum are wrong");
+    };
+
+    let ttl = if absolute && ttl > 0 {
+        let remaining = ttl - unix_millis();
+        if remaining <= 0 {
+            keyspace.remove(key);
+            return ok();
+        }
+        remaining
+    } else {
+        ttl
+    };
+    keyspace.set(key.clone(), value);
+    if ttl > 0 {
+        keyspace.expire(key, Duration::from_millis(ttl as u64));
+    }
+    ok()
+}
+
+pub fn rename(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, newkey] = args else {
+        return wrong_arity("rename");
+    };
+
+    if !keyspace.rename(key, newkey) {
+        return error("ERR no such key");
+    }
+    ok()
+}
+
+pub fn renamenx(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, newkey] = args else {
+        return wrong_arity("renamenx");
+    };
+
+    if !keyspace.exists(key) {
+        return error("ERR no such key");
+    }
+    if keyspace.exists(newkey) {
+        return RespMessage::Integer(0);
+    }
+
+    keyspace.rename(key, newkey);
+    RespMessage::Integer(1)
+}
+
+pub fn copy(shared: &Shared, client: &Client, args: &[Vec<u8>]) -> RespMessage {
+    let [source, destination, options @ ..] = args else {
+        return wrong_arity("copy");
+    };
+
+    let mut db = client.db;
+    let mut replace = false;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.to_ascii_uppercase().as_slice() {
+            b"DB" => {
+                let Some(index) = options.next().and_then(|index| parse_integer(index)) else {
+                    return error(NOT_AN_INTEGER);
+                };
+                if index < 0 || index as usize >= shared.store.databases() {
+                    return error("ERR DB index is out of range");
+                }
+                db = index as usize;
+            }
+            b"REPLACE" => replace = true,
+            _ => return error(SYNTAX_ERROR),
+        }
+    }
+    if db == client.db && source == destination {
+        return error("ERR source and destination objects are the same");
+    }
+
+    let copied = if db == client.db {
+        let mut keyspace = shared.store.lock_keys(db, &[source, destination]);
+        let entry = copied_entry(&mut keyspace, source);
+        copy_into(&mut keyspace, destination, entry, replace)
+    } else {
+        let entry = copied_entry(&mut shared.store.lock_keys(client.db, &[source]), source);
+        let mut keyspace = shared.store.lock_keys(db, &[destination]);
+        copy_into(&mut keyspace, destination, entry, replace)
+    };
+    RespMessage::Integer(copied as i64)
+}
+
+fn copied_entry(keyspace: &mut Keyspace, key: &[u8]) -> Option<(Value, Option<Duration>)> {
+    let value = keyspace.get(key)?.clone();
+    let ttl = keyspace.ttl(key).flatten();
+    Some((value, ttl))
+}
+
+fn copy_into(
+    keyspace: &mut Keyspace,
+    key: &[u8],
+    entry: Option<(Value, Option<Duration>)>,
+    replace: bool,
+) -> bool {
+    let Some((value, ttl)) = entry else {
+        return false;
+    };
+    if !replace && keyspace.exists(key) {
+        return false;
+    }
+
+    keyspace.set(key.to_vec(), value);
+    if let Some(ttl) = ttl {
+        keyspace.expire(key, ttl);
+    }
+    true
+}
+
+pub fn scan(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [cursor, options @ ..] = args else {
+        return wrong_arity("scan");
+    };
+    let Some(cursor) = std::str::from_utf8(cursor)
+        .ok()
+        .and_then(|cursor| cursor.parse::<u64>().ok())
+    else {
+        return error("ERR invalid cursor");
+    };
+
+    let mut pattern = None;
+    let mut count = DEFAULT_SCAN_COUNT;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let Some(value) = options.next() else {
+            return error(SYNTAX_ERROR);
+        };
+        if option.eq_ignore_ascii_case(b"MATCH") {
+            pattern = Some(value);
+        } else if option.eq_ignore_ascii_case(b"COUNT") {
+            match parse_integer(value) {
+                Some(n) if n > 0 => count = n as usize,
+                Some(_) => return error(SYNTAX_ERROR),
+                None => return error(NOT_AN_INTEGER),
+            }
+        } else {
+            return error(SYNTAX_ERROR);
+        }
+    }
+
+    let (next_cursor, keys) = keyspace.scan(cursor, count);
+    let keys = keys
+        .into_iter()
+        .filter(|key| pattern.is_none_or(|pattern| glob_match(pattern, key)))
+        .map(RespMessage::BulkString)
+        .collect();
+
+    RespMessage::Array(vec![
+        RespMessage::BulkString(next_cursor.to_string().into_bytes()),
+        RespMessage::Array(keys),
+    ])
+}
+
+pub fn dbsize(keyspace: &mut Keyspace) -> RespMessage {
+    RespMessage::Integer(keyspace.len() as i64)
+}
+
+pub fn randomkey(shared: &Shared, keyspace: &mut Keyspace) -> RespMessage {
+    match keyspace.random_key(&mut *shared.rng.lock().unwrap()) {
+        Some(key) => RespMessage::BulkString(key),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn flushdb(keyspace: &mut Keyspace) -> RespMessage {
+    keyspace.clear();
+    ok()
+}
+
+pub fn flushall(shared: &Shared) -> RespMessage {
+    for db in 0..shared.store.databases() {
+        shared.store.lock(db).clear();
+    }
+    ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::commands::{command, dispatch, run};
+    use crate::connection::Client;
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+    use crate::store::{Members, Value};
+
+    #[test]
+    fn test_del_counts_present_keys() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "a", "1"]));
+        run(&shared, command(&["RPUSH", "b", "1"]));
+
+        assert_eq!(
+            run(&shared, command(&["DEL", "a", "missing", "b", "a"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(run(&shared, command(&["GET", "a"])), RespMessage::Null);
+    }
+
+    #[test]
+    fn test_del_clears_expiry() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "a", "1"]));
+        run(&shared, command(&["EXPIRE", "a", "100"]));
+        run(&shared, command(&["DEL", "a"]));
+        run(&shared, command(&["SET", "a", "2"]));
+
+        assert_eq!(
+            run(&shared, command(&["TTL", "a"])),
+            RespMessage::Integer(-1)
+        );
+    }
+
+    #[test]
+    fn test_exists_single_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "foo"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "missing"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_exists_counts_duplicates() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "foo", "foo", "missing"])),
+            RespMessage::Integer(2)
+        );
+    }
+
+    #[test]
+    fn test_exists_ignores_expired_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+        shared
+            .store
+            .lock(0)
+            .expire(b"foo", Duration::from_millis(10));
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "foo"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_ttl_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["TTL", "foo"])),
+            RespMessage::Integer(-2)
+        );
+    }
+
+    #[test]
+    fn test_expire_and_ttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(-1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(100)
+        );
+    }
+
+    #[test]
+    fn test_expire_nx_and_xx() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100", "XX"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(-1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100", "NX"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "200", "nx"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "200", "XX"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(200)
+        );
+    }
+
+    #[test]
+    fn test_expire_gt_and_lt() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100", "GT"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100", "LT"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "200", "LT"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "50", "GT"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "200", "GT"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "50", "LT"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(50)
+        );
+    }
+
+    #[test]
+    fn test_expire_conflicting_flags() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        for flags in [["NX", "XX"], ["GT", "LT"], ["NX", "GT"]] {
+            assert_eq!(
+                run(
+                    &shared,
+                    command(&["EXPIRE", "foo", "100", flags[0], flags[1]])
+                ),
+                RespMessage::Error("ERR syntax error".to_string())
+            );
+        }
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "100", "SOON"])),
+            RespMessage::Error("ERR Unsupported option SOON".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(-1)
+        );
+    }
+
+    #[test]
+    fn test_expire_overflowing_ttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", &i64::MAX.to_string()])),
+            RespMessage::Error("ERR invalid expire time in 'expire' command".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(-1)
+        );
+    }
+
+    #[test]
+    fn test_expire_after_rejects_overflowing_deadline() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar", "EX", "100"]));
+
+        let mut keyspace = shared.store.lock(0);
+        assert_eq!(
+            super::expire_after(
+                &mut keyspace,
+                b"foo",
+                Some(Duration::MAX),
+                super::ExpireCondition::IfGreater
+            ),
+            None
+        );
+        assert!(keyspace.ttl(b"foo").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_expire_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["EXPIRE", "foo", "100"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_expire_non_positive_deletes_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXPIRE", "foo", "0"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+    }
+
+    fn sorted_keys(reply: RespMessage) -> Vec<RespMessage> {
+        let RespMessage::Array(mut keys) = reply else {
+            panic!("expected an array");
+        };
+        keys.sort_by_key(|key| format!("{:?}", key));
+        keys
+    }
+
+    #[test]
+    fn test_keys_matches_pattern() {
+        let shared = Shared::default();
+        for key in ["user:1", "user:2", "session:1"] {
+            run(&shared, command(&["SET", key, "x"]));
+        }
+
+        assert_eq!(
+            sorted_keys(run(&shared, command(&["KEYS", "user:*"]))),
+            vec![
+                RespMessage::BulkString(b"user:1".to_vec()),
+                RespMessage::BulkString(b"user:2".to_vec()),
+            ]
+        );
+        assert_eq!(sorted_keys(run(&shared, command(&["KEYS", "*"]))).len(), 3);
+    }
+
+    #[test]
+    fn test_keys_skips_expired() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "live", "x"]));
+        run(&shared, command(&["SET", "expired", "x"]));
+        shared
+            .store
+            .lock(0)
+            .expire(b"expired", Duration::from_millis(10));
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(
+            run(&shared, command(&["KEYS", "*"])),
+            RespMessage::Array(vec![RespMessage::BulkString(b"live".to_vec())])
+        );
+    }
+
+    #[test]
+    fn test_dbsize_skips_expired() {
+        let shared = Shared::default();
+        for key in ["a", "b", "expired"] {
+            run(&shared, command(&["SET", key, "x"]));
+        }
+        shared
+            .store
+            .lock(0)
+            .expire(b"expired", Duration::from_millis(10));
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(run(&shared, command(&["DBSIZE"])), RespMessage::Integer(2));
+    }
+
+    #[test]
+    fn test_randomkey_empty_db() {
+        assert_eq!(
+            run(&Shared::default(), command(&["RANDOMKEY"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_randomkey_is_deterministic_with_seed() {
+        let seeded = || {
+            let shared = Shared {
+                rng: Arc::new(Mutex::new(StdRng::seed_from_u64(7))),
+                ..Shared::default()
+            };
+            for key in ["a", "b", "c", "expired"] {
+                run(&shared, command(&["SET", key, "x"]));
+            }
+            shared
+                .store
+                .lock(0)
+                .expire(b"expired", Duration::from_millis(10));
+            shared
+        };
+        let (first, second) = (seeded(), seeded());
+        std::thread::sleep(Duration::from_millis(30));
+
+        for _ in 0..10 {
+            let key = run(&first, command(&["RANDOMKEY"]));
+            assert!(
+                ["a", "b", "c"]
+                    .map(|key| RespMessage::BulkString(key.as_bytes().to_vec()))
+                    .contains(&key)
+            );
+            assert_eq!(run(&second, command(&["RANDOMKEY"])), key);
+        }
+    }
+
+    #[test]
+    fn test_copy() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "source", "a", "b"]));
+        run(&shared, command(&["EXPIRE", "source", "100"]));
+
+        assert_eq!(
+            run(&shared, command(&["COPY", "source", "destination"])),
+            RespMessage::Integer(1)
+        );
+        run(&shared, command(&["RPUSH", "source", "c"]));
+
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "destination", "0", "-1"])),
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"a".to_vec()),
+                RespMessage::BulkString(b"b".to_vec()),
+            ])
+        );
+        assert!(matches!(
+            run(&shared, command(&["TTL", "destination"])),
+            RespMessage::Integer(ttl) if ttl > 0
+        ));
+        assert_eq!(
+            run(&shared, command(&["COPY", "missing", "other"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_copy_existing_destination() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "source", "new"]));
+        run(&shared, command(&["SET", "destination", "old"]));
+
+        assert_eq!(
+            run(&shared, command(&["COPY", "source", "destination"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "destination"])),
+            RespMessage::BulkString(b"old".to_vec())
+        );
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["COPY", "source", "destination", "REPLACE"])
+            ),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "destination"])),
+            RespMessage::BulkString(b"new".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_copy_to_another_db() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["COPY", "foo", "foo", "DB", "1"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            shared.store.lock(1).get(b"foo"),
+            Some(&Value::String(b"bar".to_vec()))
+        );
+        assert!(matches!(
+            run(&shared, command(&["COPY", "foo", "foo"])),
+            RespMessage::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_pttl_matches_ttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+        run(&shared, command(&["EXPIRE", "foo", "100"]));
+
+        let RespMessage::Integer(ttl) = run(&shared, command(&["TTL", "foo"])) else {
+            panic!("expected an integer");
+        };
+        let RespMessage::Integer(pttl) = run(&shared, command(&["PTTL", "foo"])) else {
+            panic!("expected an integer");
+        };
+
+        assert_eq!(ttl, 100);
+        assert!((ttl * 1000 - pttl).abs() < 1000, "{} vs {}", ttl, pttl);
+        assert_eq!(
+            run(&shared, command(&["PTTL", "missing"])),
+            RespMessage::Integer(-2)
+        );
+    }
+
+    #[test]
+    fn test_expireat_agrees_with_pexpireat() {
+        let shared = Shared::default();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        run(&shared, command(&["SET", "seconds", "x"]));
+        run(&shared, command(&["SET", "millis", "x"]));
+
+        let at = (now.as_secs() + 100).to_string();
+        assert_eq!(
+            run(&shared, command(&["EXPIREAT", "seconds", &at])),
+            RespMessage::Integer(1)
+        );
+        let at = ((now.as_secs() + 100) * 1000).to_string();
+        assert_eq!(
+            run(&shared, command(&["PEXPIREAT", "millis", &at])),
+            RespMessage::Integer(1)
+        );
+
+        let seconds = run(&shared, command(&["PTTL", "seconds"]));
+        let millis = run(&shared, command(&["PTTL", "millis"]));
+        let (RespMessage::Integer(seconds), RespMessage::Integer(millis)) = (seconds, millis)
+        else {
+            panic!("expected integers");
+        };
+        assert!((seconds - millis).abs() < 50, "{} vs {}", seconds, millis);
+        assert!(seconds > 98_000 && seconds <= 100_000);
+    }
+
+    #[test]
+    fn test_expireat_in_the_past_deletes_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXPIREAT", "foo", "1"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+        assert_eq!(
+            run(&shared, command(&["PEXPIREAT", "foo", "1"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    fn scan_all(shared: &Shared, options: &[&str]) -> Vec<RespMessage> {
+        let mut seen = Vec::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let mut cmd = vec!["SCAN", &cursor];
+            cmd.extend_from_slice(options);
+            let RespMessage::Array(reply) = run(shared, command(&cmd)) else {
+                panic!("expected an array");
+            };
+            let [RespMessage::BulkString(next), RespMessage::Array(keys)] = &reply[..] else {
+                panic!("unexpected reply shape");
+            };
+            seen.extend(keys.iter().cloned());
+            cursor = String::from_utf8(next.clone()).unwrap();
+            if cursor == "0" {
+                break;
+            }
+        }
+        sorted_keys(RespMessage::Array(seen))
+    }
+
+    #[test]
+    fn test_scan_visits_all_keys() {
+        let shared = Shared::default();
+        for i in 0..25 {
+            run(&shared, command(&["SET", &format!("key:{}", i), "x"]));
+        }
+
+        let seen = scan_all(&shared, &["COUNT", "4"]);
+
+        assert_eq!(seen.len(), 25);
+        assert_eq!(seen, sorted_keys(run(&shared, command(&["KEYS", "*"]))));
+    }
+
+    #[test]
+    fn test_scan_match() {
+        let shared = Shared::default();
+        for key in ["user:1", "user:2", "session:1"] {
+            run(&shared, command(&["SET", key, "x"]));
+        }
+
+        assert_eq!(
+            scan_all(&shared, &["MATCH", "user:*", "COUNT", "1"]),
+            vec![
+                RespMessage::BulkString(b"user:1".to_vec()),
+                RespMessage::BulkString(b"user:2".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_invalid_option() {
+        assert_eq!(
+            run(&Shared::default(), command(&["SCAN", "0", "LIMIT", "1"])),
+            RespMessage::Error("ERR syntax error".to_string())
+        );
+    }
+
+    #[test]
+    fn test_persist_removes_ttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+        shared
+            .store
+            .lock(0)
+            .expire(b"foo", Duration::from_millis(10));
+
+        assert_eq!(
+            run(&shared, command(&["PERSIST", "foo"])),
+            RespMessage::Integer(1)
+        );
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["PERSIST", "foo"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["PERSIST", "missing"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_object_encoding() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "int", "12345"]));
+        run(&shared, command(&["SET", "short", "hello"]));
+        run(&shared, command(&["SET", "long", &"x".repeat(100)]));
+        run(&shared, command(&["RPUSH", "list", "a", "b"]));
+        run(&shared, command(&["SADD", "set", "1", "2"]));
+
+        for (key, expected) in [
+            ("int", "int"),
+            ("short", "embstr"),
+            ("long", "raw"),
+            ("list", "listpack"),
+            ("set", "intset"),
+        ] {
+            assert_eq!(
+                run(&shared, command(&["OBJECT", "ENCODING", key])),
+                RespMessage::SimpleString(expected.to_string()),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_object_refcount_and_idletime() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["OBJECT", "REFCOUNT", "foo"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["OBJECT", "IDLETIME", "foo"])),
+            RespMessage::Integer(0)
+        );
+        for subcommand in ["REFCOUNT", "IDLETIME"] {
+            assert_eq!(
+                run(&shared, command(&["OBJECT", subcommand, "missing"])),
+                RespMessage::Error("ERR no such key".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_object_encoding_missing_key() {
+        assert_eq!(
+            run(
+                &Shared::default(),
+                command(&["OBJECT", "ENCODING", "missing"])
+            ),
+            RespMessage::Error("ERR no such key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_object_help() {
+        let RespMessage::Array(lines) = run(&Shared::default(), command(&["OBJECT", "HELP"]))
+        else {
+            panic!("expected an array");
+        };
+
+        assert!(!lines.is_empty());
+        assert!(
+            lines
+                .iter()
+                .all(|line| matches!(line, RespMessage::SimpleString(_)))
+        );
+        assert!(lines.contains(&RespMessage::SimpleString("IDLETIME <key>".to_string())));
+    }
+
+    fn dump_and_restore(shared: &Shared, key: &str) {
+        let RespMessage::BulkString(payload) = run(shared, command(&["DUMP", key])) else {
+            panic!("expected a payload for {}", key);
+        };
+        let original = shared.store.lock(0).get(key.as_bytes()).cloned();
+
+        run(shared, command(&["DEL", key]));
+        assert_eq!(
+            run(
+                shared,
+                RespMessage::Array(vec![
+                    RespMessage::BulkString(b"RESTORE".to_vec()),
+                    RespMessage::BulkString(key.as_bytes().to_vec()),
+                    RespMessage::BulkString(b"0".to_vec()),
+                    RespMessage::BulkString(payload),
+                ])
+            ),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(shared.store.lock(0).get(key.as_bytes()).cloned(), original);
+    }
+
+    #[test]
+    fn test_dump_and_restore_round_trip() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "string", "value"]));
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c"]));
+        run(&shared, command(&["HSET", "hash", "f1", "v1", "f2", "v2"]));
+        run(&shared, command(&["SADD", "set", "a", "b"]));
+        run(&shared, command(&["ZADD", "zset", "1.5", "a", "-2", "b"]));
+
+        for key in ["string", "list", "hash", "set", "zset"] {
+            dump_and_restore(&shared, key);
+        }
+        assert_eq!(
+            run(&shared, command(&["DUMP", "missing"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_restore_existing_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "old"]));
+        let RespMessage::BulkString(payload) = run(&shared, command(&["DUMP", "foo"])) else {
+            panic!("expected a payload");
+        };
+        let restore = |options: &[&[u8]]| {
+            let mut parts = vec![
+                RespMessage::BulkString(b"RESTORE".to_vec()),
+                RespMessage::BulkString(b"foo".to_vec()),
+                RespMessage::BulkString(b"5000".to_vec()),
+                RespMessage::BulkString(payload.clone()),
+            ];
+            parts.extend(
+                options
+                    .iter()
+                    .map(|option| RespMessage::BulkString(option.to_vec())),
+            );
+            run(&shared, RespMessage::Array(parts))
+        };
+
+        assert_eq!(
+            restore(&[]),
+            RespMessage::Error("BUSYKEY Target key name already exists.".to_string())
+        );
+        assert_eq!(
+            restore(&[b"REPLACE"]),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert!(matches!(
+            run(&shared, command(&["PTTL", "foo"])),
+            RespMessage::Integer(ttl) if ttl > 0 && ttl <= 5000
+        ));
+    }
+
+    #[test]
+    fn test_restore_absttl() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+        let RespMessage::BulkString(payload) = run(&shared, command(&["DUMP", "foo"])) else {
+            panic!("expected a payload");
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let restore = |key: &[u8], at: u64| {
+            run(
+                &shared,
+                RespMessage::Array(vec![
+                    RespMessage::BulkString(b"RESTORE".to_vec()),
+                    RespMessage::BulkString(key.to_vec()),
+                    RespMessage::BulkString(at.to_string().into_bytes()),
+                    RespMessage::BulkString(payload.clone()),
+                    RespMessage::BulkString(b"ABSTTL".to_vec()),
+                ]),
+            )
+        };
+
+        assert_eq!(
+            restore(b"later", now + 5000),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert!(matches!(
+            run(&shared, command(&["PTTL", "later"])),
+            RespMessage::Integer(ttl) if ttl > 0 && ttl <= 5000
+        ));
+        assert_eq!(
+            restore(b"past", 1),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "past"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_restore_rejects_corrupted_payload() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+        let RespMessage::BulkString(mut payload) = run(&shared, command(&["DUMP", "foo"])) else {
+            panic!("expected a payload");
+        };
+        payload[1] ^= 0xFF;
+
+        assert_eq!(
+            run(
+                &shared,
+                RespMessage::Array(vec![
+                    RespMessage::BulkString(b"RESTORE".to_vec()),
+                    RespMessage::BulkString(b"copy".to_vec()),
+                    RespMessage::BulkString(b"0".to_vec()),
+                    RespMessage::BulkString(payload),
+                ])
+            ),
+            RespMessage::Error("ERR DUMP payload version or checksum are wrong".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rename_overwrites_destination() {
+        let shared = Shared::default();
+        run(&shared, command(&["SETEX", "foo", "100", "1"]));
+        run(&shared, command(&["RPUSH", "bar", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["RENAME", "foo", "bar"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "bar"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "bar"])),
+            RespMessage::Integer(100)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "foo"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_rename_missing_source() {
+        let shared = Shared::default();
+
+        for name in ["RENAME", "RENAMENX"] {
+            assert_eq!(
+                run(&shared, command(&[name, "missing", "bar"])),
+                RespMessage::Error("ERR no such key".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_renamenx() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "1"]));
+        run(&shared, command(&["SET", "bar", "2"]));
+
+        assert_eq!(
+            run(&shared, command(&["RENAMENX", "foo", "bar"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["RENAMENX", "foo", "baz"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "baz"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "bar"])),
+            RespMessage::BulkString(b"2".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "string", "x"]));
+        run(&shared, command(&["RPUSH", "list", "x"]));
+        run(&shared, command(&["HSET", "hash", "f", "x"]));
+        shared.store.lock(0).set(
+            b"set".to_vec(),
+            Value::Set(Members::from_iter([b"x".to_vec()])),
+        );
+
+        for (key, expected) in [
+            ("string", "string"),
+            ("list", "list"),
+            ("hash", "hash"),
+            ("set", "set"),
+            ("missing", "none"),
+        ] {
+            assert_eq!(
+                run(&shared, command(&["TYPE", key])),
+                RespMessage::SimpleString(expected.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_flushdb_clears_selected_database() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["SET", "foo", "0"]));
+        dispatch(&shared, &mut client, command(&["SELECT", "1"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "1"]));
+        dispatch(&shared, &mut client, command(&["SETEX", "bar", "100", "1"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["FLUSHDB"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert!(shared.store.lock(1).is_empty());
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["TTL", "bar"])),
+            RespMessage::Integer(-2)
+        );
+        assert!(shared.store.lock(0).exists(b"foo"));
+    }
+
+    #[test]
+    fn test_flushall_clears_every_database() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["SETEX", "foo", "100", "0"]));
+        dispatch(&shared, &mut client, command(&["SELECT", "1"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "1"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["FLUSHALL"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        for db in 0..shared.store.databases() {
+            assert!(shared.store.lock(db).is_empty());
+        }
+    }
+}
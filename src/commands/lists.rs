@@ -0,0 +1,719 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    commands::{
+        NOT_AN_INTEGER, SYNTAX_ERROR, WRONG_TYPE, error, normalize_range, ok, parse_float,
+        parse_integer, table::Block, wrong_arity,
+    },
+    parser::RespMessage,
+    store::{Keyspace, Value},
+};
+
+#[derive(Clone, Copy)]
+pub enum End {
+    Left,
+    Right,
+}
+
+impl End {
+    fn parse(name: &[u8]) -> Option<Self> {
+        if name.eq_ignore_ascii_case(b"LEFT") {
+            Some(End::Left)
+        } else if name.eq_ignore_ascii_case(b"RIGHT") {
+            Some(End::Right)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn push(keyspace: &mut Keyspace, args: &[Vec<u8>], end: End, name: &str) -> RespMessage {
+    let [key, elements @ ..] = args else {
+        return wrong_arity(name);
+    };
+    let Value::List(list) = keyspace.get_or_insert_with(key, || Value::List(VecDeque::new()))
+    else {
+        return error(WRONG_TYPE);
+    };
+
+    for element in elements {
+        match end {
+            End::Left => list.push_front(element.clone()),
+            End::Right => list.push_back(element.clone()),
+        }
+    }
+
+    RespMessage::Integer(list.len() as i64)
+}
+
+pub fn pop(keyspace: &mut Keyspace, args: &[Vec<u8>], end: End, name: &str) -> RespMessage {
+    let (key, count) = match args {
+        [key] => (key, None),
+        [key, count] => match parse_integer(count) {
+            Some(count) if count >= 0 => (key, Some(count as usize)),
+            _ => return error("ERR value is out of range, must be positive"),
+        },
+        _ => return wrong_arity(name),
+    };
+
+    let list = match keyspace.get_mut(key) {
+        Some(Value::List(list)) => list,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Null,
+    };
+
+    let mut popped = Vec::new();
+    while popped.len() < count.unwrap_or(1) {
+        let element = match end {
+            End::Left => list.pop_front(),
+            End::Right => list.pop_back(),
+        };
+        match element {
+            Some(element) => popped.push(RespMessage::BulkString(element)),
+            None => break,
+        }
+    }
+    if list.is_empty() {
+        keyspace.remove(key);
+    }
+
+    match count {
+        Some(_) => RespMessage::Array(popped),
+        None => popped.pop().unwrap_or(RespMessage::Null),
+    }
+}
+
+pub fn blocking_pop(
+    keyspace: &mut Keyspace,
+    args: &[Vec<u8>],
+    end: End,
+    name: &str,
+) -> RespMessage {
+    let [keys @ .., timeout] = args else {
+        return wrong_arity(name);
+    };
+    if let Err(reply) = parse_timeout(timeout) {
+        return reply;
+    }
+
+    for key in keys {
+        let list = match keyspace.get_mut(key) {
+            Some(Value::List(list)) => list,
+            Some(_) => return error(WRONG_TYPE),
+            None => continue,
+        };
+        let element = match end {
+            End::Left => list.pop_front(),
+            End::Right => list.pop_back(),
+        };
+        let Some(element) = element else {
+            continue;
+        };
+        if list.is_empty() {
+            keyspace.remove(key);
+        }
+        return RespMessage::Array(vec![
+            RespMessage::BulkString(key.clone()),
+            RespMessage::BulkString(element),
+        ]);
+    }
+
+    RespMessage::Null
+}
+
+pub fn block_timeout(args: &[Vec<u8>]) -> Option<Block> {
+    if args.len() < 2 {
+        return None;
+    }
+
+    parse_timeout(args.last()?).ok().map(Block::Pop)
+}
+
+fn parse_timeout(timeout: &[u8]) -> Result<Duration, RespMessage> {
+    let Some(seconds) = parse_float(timeout).filter(|seconds| seconds.is_finite()) else {
+        return Err(error("ERR timeout is not a float or out of range"));
+    };
+    if seconds < 0.0 {
+        return Err(error("ERR timeout is negative"));
+    }
+
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| error("ERR timeout is not a float or out of range"))
+}
+
+pub fn llen(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("llen");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::List(list)) => RespMessage::Integer(list.len() as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn lindex(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, index] = args else {
+        return wrong_arity("lindex");
+    };
+    let Some(index) = parse_integer(index) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let list = match keyspace.get(key) {
+        Some(Value::List(list)) => list,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Null,
+    };
+    let index = if index < 0 {
+        list.len() as i64 + index
+    } else {
+        index
+    };
+
+    match usize::try_from(index)
+        .ok()
+        .and_then(|index| list.get(index))
+    {
+        Some(element) => RespMessage::BulkString(element.clone()),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn lrange(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, start, stop] = args else {
+        return wrong_arity("lrange");
+    };
+    let (Some(start), Some(stop)) = (parse_integer(start), parse_integer(stop)) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let list = match keyspace.get(key) {
+        Some(Value::List(list)) => list,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Array(vec![]),
+    };
+
+    let elements = match normalize_range(start, stop, list.len()) {
+        Some((start, stop)) => list
+            .range(start..=stop)
+            .map(|element| RespMessage::BulkString(element.clone()))
+            .collect(),
+        None => vec![],
+    };
+
+    RespMessage::Array(elements)
+}
+
+pub fn lmove(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [source, destination, from, to] = args else {
+        return wrong_arity("lmove");
+    };
+    let (Some(from), Some(to)) = (End::parse(from), End::parse(to)) else {
+        return error(SYNTAX_ERROR);
+    };
+
+    match keyspace.get(source) {
+        Some(Value::List(_)) => {}
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Null,
+    }
+    if let Some(value) = keyspace.get(destination)
+        && !matches!(value, Value::List(_))
+    {
+        return error(WRONG_TYPE);
+    }
+
+    let Some(Value::List(list)) = keyspace.get_mut(source) else {
+        return RespMessage::Null;
+    };
+    let element = match from {
+        End::Left => list.pop_front(),
+        End::Right => list.pop_back(),
+    };
+    let Some(element) = element else {
+        return RespMessage::Null;
+    };
+    if list.is_empty() {
+        keyspace.remove(source);
+    }
+
+    let Value::List(list) =
+        keyspace.get_or_insert_with(destination, || Value::List(VecDeque::new()))
+    else {
+        return error(WRONG_TYPE);
+    };
+    match to {
+        End::Left => list.push_front(element.clone()),
+        End::Right => list.push_back(element.clone()),
+    }
+
+    RespMessage::BulkString(element)
+}
+
+pub fn lrem(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, count, element] = args else {
+        return wrong_arity("lrem");
+    };
+    let Some(count) = parse_integer(count) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let list = match keyspace.get_mut(key) {
+        Some(Value::List(list)) => list,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Integer(0),
+    };
+
+    let limit = match count {
+        0 => usize::MAX,
+        count => usize::try_from(count.unsigned_abs()).unwrap_or(usize::MAX),
+    };
+    let mut removed = 0;
+    if count < 0 {
+        let mut index = list.len();
+        while index > 0 && removed < limit {
+            index -= 1;
+            if list[index] == *element {
+                list.remove(index);
+                removed += 1;
+            }
+        }
+    } else {
+        let mut index = 0;
+        while index < list.len() && removed < limit {
+            if list[index] == *element {
+                list.remove(index);
+                removed += 1;
+            } else {
+                index += 1;
+            }
+        }
+    }
+    if list.is_empty() {
+        keyspace.remove(key);
+    }
+
+    RespMessage::Integer(removed as i64)
+}
+
+pub fn ltrim(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, start, stop] = args else {
+        return wrong_arity("ltrim");
+    };
+    let (Some(start), Some(stop)) = (parse_integer(start), parse_integer(stop)) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let list = match keyspace.get_mut(key) {
+        Some(Value::List(list)) => list,
+        Some(_) => return error(WRONG_TYPE),
+        None => return ok(),
+    };
+
+    match normalize_range(start, stop, list.len()) {
+        Some((start, stop)) => {
+            list.truncate(stop + 1);
+            list.drain(..start);
+        }
+        None => list.clear(),
+    }
+    if list.is_empty() {
+        keyspace.remove(key);
+    }
+
+    ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    fn bulk_strings(items: &[&str]) -> RespMessage {
+        RespMessage::Array(
+            items
+                .iter()
+                .map(|item| RespMessage::BulkString(item.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_push_both_ends() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["RPUSH", "list", "b", "c"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["LPUSH", "list", "a", "z"])),
+            RespMessage::Integer(4)
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&["z", "a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn test_lrange_negative_indices() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c", "d"]));
+
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "-3", "-2"])),
+            bulk_strings(&["b", "c"])
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "-100", "100"])),
+            bulk_strings(&["a", "b", "c", "d"])
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "3", "1"])),
+            bulk_strings(&[])
+        );
+    }
+
+    #[test]
+    fn test_lrange_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&[])
+        );
+    }
+
+    #[test]
+    fn test_pop_single_element() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c"]));
+
+        assert_eq!(
+            run(&shared, command(&["LPOP", "list"])),
+            RespMessage::BulkString(b"a".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["RPOP", "list"])),
+            RespMessage::BulkString(b"c".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["LPOP", "missing"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_pop_with_count() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c", "d"]));
+
+        assert_eq!(
+            run(&shared, command(&["LPOP", "list", "2"])),
+            bulk_strings(&["a", "b"])
+        );
+        assert_eq!(
+            run(&shared, command(&["RPOP", "list", "5"])),
+            bulk_strings(&["d", "c"])
+        );
+        assert_eq!(
+            run(&shared, command(&["LPOP", "list", "-1"])),
+            RespMessage::Error("ERR value is out of range, must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pop_removes_empty_list() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a"]));
+        run(&shared, command(&["RPOP", "list"]));
+
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "list"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["RPOP", "list", "1"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_llen() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b"]));
+        run(&shared, command(&["SET", "string", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["LLEN", "list"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["LLEN", "missing"])),
+            RespMessage::Integer(0)
+        );
+        assert!(matches!(
+            run(&shared, command(&["LLEN", "string"])),
+            RespMessage::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_lindex() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c"]));
+
+        for (index, expected) in [("0", "a"), ("2", "c"), ("-1", "c"), ("-3", "a")] {
+            assert_eq!(
+                run(&shared, command(&["LINDEX", "list", index])),
+                RespMessage::BulkString(expected.as_bytes().to_vec())
+            );
+        }
+        for index in ["3", "-4"] {
+            assert_eq!(
+                run(&shared, command(&["LINDEX", "list", index])),
+                RespMessage::Null
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["LPUSH", "foo", "a"])),
+            RespMessage::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_lmove_left_left() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "source", "a", "b"]));
+        run(&shared, command(&["RPUSH", "destination", "x"]));
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["LMOVE", "source", "destination", "LEFT", "LEFT"])
+            ),
+            RespMessage::BulkString(b"a".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "source", "0", "-1"])),
+            bulk_strings(&["b"])
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "destination", "0", "-1"])),
+            bulk_strings(&["a", "x"])
+        );
+    }
+
+    #[test]
+    fn test_lmove_right_left() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "c"]));
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["LMOVE", "list", "list", "right", "left"])
+            ),
+            RespMessage::BulkString(b"c".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&["c", "a", "b"])
+        );
+
+        run(
+            &shared,
+            command(&["LMOVE", "list", "other", "RIGHT", "LEFT"]),
+        );
+        run(
+            &shared,
+            command(&["LMOVE", "list", "other", "RIGHT", "LEFT"]),
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "other", "0", "-1"])),
+            bulk_strings(&["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_lmove_empty_source() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "string", "x"]));
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["LMOVE", "missing", "destination", "LEFT", "RIGHT"])
+            ),
+            RespMessage::Null
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "destination"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(
+                &shared,
+                command(&["LMOVE", "missing", "string", "LEFT", "UP"])
+            ),
+            RespMessage::Error("ERR syntax error".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lmove_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a"]));
+        run(&shared, command(&["SET", "string", "x"]));
+
+        for (source, destination) in [("string", "list"), ("list", "string")] {
+            assert_eq!(
+                run(
+                    &shared,
+                    command(&["LMOVE", source, destination, "LEFT", "LEFT"])
+                ),
+                RespMessage::Error(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+                )
+            );
+        }
+        assert_eq!(
+            run(&shared, command(&["LLEN", "list"])),
+            RespMessage::Integer(1)
+        );
+    }
+
+    #[test]
+    fn test_blocking_pop_available_element() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "second", "a", "b"]));
+
+        assert_eq!(
+            run(&shared, command(&["BLPOP", "first", "second", "0"])),
+            bulk_strings(&["second", "a"])
+        );
+        assert_eq!(
+            run(&shared, command(&["BRPOP", "first", "second", "0.5"])),
+            bulk_strings(&["second", "b"])
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "second"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["BLPOP", "first", "second", "0"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_blocking_pop_invalid_timeout() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["BLPOP", "list", "soon"])),
+            RespMessage::Error("ERR timeout is not a float or out of range".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["BRPOP", "list", "-1"])),
+            RespMessage::Error("ERR timeout is negative".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lrem_from_head() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["RPUSH", "list", "a", "b", "a", "c", "a"]),
+        );
+
+        assert_eq!(
+            run(&shared, command(&["LREM", "list", "2", "a"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&["b", "c", "a"])
+        );
+    }
+
+    #[test]
+    fn test_lrem_from_tail() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["RPUSH", "list", "a", "b", "a", "c", "a"]),
+        );
+
+        assert_eq!(
+            run(&shared, command(&["LREM", "list", "-2", "a"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn test_lrem_all_occurrences() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a", "b", "a"]));
+        run(&shared, command(&["SET", "string", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["LREM", "list", "0", "a"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["LREM", "list", "0", "b"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "list"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["LREM", "string", "0", "x"])),
+            RespMessage::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_ltrim_to_sub_range() {
+        let shared = Shared::default();
+        run(
+            &shared,
+            command(&["RPUSH", "list", "a", "b", "c", "d", "e"]),
+        );
+
+        assert_eq!(
+            run(&shared, command(&["LTRIM", "list", "1", "-2"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["LRANGE", "list", "0", "-1"])),
+            bulk_strings(&["b", "c", "d"])
+        );
+
+        run(&shared, command(&["LTRIM", "list", "5", "10"]));
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "list"])),
+            RespMessage::Integer(0)
+        );
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    fmt::Write,
+    sync::atomic::Ordering,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    commands::{
+        NOT_A_FLOAT, NOT_AN_INTEGER, REDIS_VERSION, error, help, ok, parse_float, parse_integer,
+        table::commands, unknown_subcommand, wrong_arity,
+    },
+    config::PARAMETERS,
+    glob::glob_match,
+    parser::RespMessage,
+    rdb,
+    server::Shared,
+};
+
+const SECTIONS: [&str; 5] = ["server", "clients", "memory", "stats", "keyspace"];
+
+pub fn info(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    let sections: Vec<&str> = match args {
+        [] => SECTIONS.to_vec(),
+        [section] => {
+            let section = String::from_utf8_lossy(section).to_lowercase();
+            match section.as_str() {
+                "all" | "default" | "everything" => SECTIONS.to_vec(),
+                _ => SECTIONS
+                    .into_iter()
+                    .filter(|&known| known == section)
+                    .collect(),
+            }
+        }
+        _ => return wrong_arity("info"),
+    };
+
+    let store_stats = shared.store.stats();
+    let mut report = String::new();
+    for section in sections {
+        if !report.is_empty() {
+            report.push_str("\r\n");
+        }
+        match section {
+            "server" => {
+                report.push_str("# Server\r\n");
+                let _ = write!(report, "redis_version:{}\r\n", REDIS_VERSION);
+                let _ = write!(
+                    report,
+                    "uptime_in_seconds:{}\r\n",
+                    shared.stats.started_at.elapsed().as_secs()
+                );
+            }
+            "clients" => {
+                report.push_str("# Clients\r\n");
+                let _ = write!(
+                    report,
+                    "connected_clients:{}\r\n",
+                    shared.stats.connected_clients.load(Ordering::Relaxed)
+                );
+            }
+            "memory" => {
+                report.push_str("# Memory\r\n");
+                let config = shared.config();
+                let _ = write!(report, "used_memory:{}\r\n", shared.store.used_memory());
+                let _ = write!(report, "maxmemory:{}\r\n", config.maxmemory);
+                let _ = write!(report, "maxmemory_policy:{}\r\n", config.maxmemory_policy);
+            }
+            "stats" => {
+                report.push_str("# Stats\r\n");
+                let _ = write!(
+                    report,
+                    "total_commands_processed:{}\r\n",
+                    shared.stats.commands_processed.load(Ordering::Relaxed)
+                );
+                let _ = write!(
+                    report,
+                    "evicted_keys:{}\r\n",
+                    shared.stats.evicted_keys.load(Ordering::Relaxed)
+                );
+            }
+            _ => {
+                report.push_str("# Keyspace\r\n");
+                for (db, stats) in store_stats.databases.iter().enumerate() {
+                    if stats.keys > 0 {
+                        let _ = write!(
+                            report,
+                            "db{}:keys={},expires={},avg_ttl=0\r\n",
+                            db, stats.keys, stats.expires
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    RespMessage::BulkString(report.into_bytes())
+}
+
+pub fn save(shared: &Shared) -> RespMessage {
+    match rdb::save(&shared.store, &shared.config().dbfilename) {
+        Ok(()) => ok(),
+        Err(e) => error(&format!("ERR {}", e)),
+    }
+}
+
+pub fn bgsave(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    if args.len() > 1 {
+        return wrong_arity("bgsave");
+    }
+
+    let snapshot = rdb::snapshot(&shared.store);
+    let path = shared.config().dbfilename.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = rdb::write_snapshot(&snapshot, &path) {
+            tracing::error!(error = %e, "background save failed");
+        }
+    });
+
+    RespMessage::SimpleString("Background saving started".to_string())
+}
+
+const COMMAND_HELP: &[&str] = &[
+    "COUNT",
+    "    Return the total number of commands in this Redis server.",
+    "DOCS <command-name> [<command-name> ...]",
+    "    Return documentation details about multiple Redis commands.",
+];
+
+const CONFIG_HELP: &[&str] = &[
+    "GET <pattern>",
+    "    Return parameters matching the glob-like <pattern> and their values.",
+    "SET <directive> <value>",
+    "    Set the configuration <directive> to <value>.",
+];
+
+const DEBUG_HELP: &[&str] = &[
+    "SET-ACTIVE-EXPIRE <0|1>",
+    "    Setting it to 0 disables expiring keys in background when they are not",
+    "    accessed (otherwise the Redis behavior). Setting it to 1 reenables back the",
+    "    default.",
+    "SLEEP <seconds>",
+    "    Stop the server for <seconds>. Decimals allowed.",
+];
+
+pub fn command(args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, names)) = args.split_first() else {
+        return RespMessage::Array(Vec::new());
+    };
+
+    match subcommand.to_ascii_uppercase().as_slice() {
+        b"COUNT" => RespMessage::Integer(commands().len() as i64),
+        b"DOCS" => RespMessage::Map(
+            commands()
+                .iter()
+                .filter(|command| {
+                    names.is_empty()
+                        || names
+                            .iter()
+                            .any(|name| name.eq_ignore_ascii_case(command.name.as_bytes()))
+                })
+                .map(|command| {
+                    let flags = command
+                        .flag_names()
+                        .into_iter()
+                        .map(|flag| RespMessage::SimpleString(flag.to_string()))
+                        .collect();
+                    (
+                        RespMessage::BulkString(command.name.to_lowercase().into_bytes()),
+                        RespMessage::Map(vec![
+                            (
+                                RespMessage::BulkString(b"arity".to_vec()),
+                                RespMessage::Integer(command.arity),
+                            ),
+                            (
+                                RespMessage::BulkString(b"flags".to_vec()),
+                                RespMessage::Array(flags),
+                            ),
+                        ]),
+                    )
+                })
+                .collect(),
+        ),
+        b"HELP" => help("COMMAND", COMMAND_HELP),
+        _ => unknown_subcommand("COMMAND", subcommand),
+    }
+}
+
+pub fn config(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, args)) = args.split_first() else {
+        return wrong_arity("config");
+    };
+
+    match (subcommand.to_ascii_uppercase().as_slice(), args) {
+        (b"GET", [_, ..]) => {
+            let config = shared.config();
+            let pairs = PARAMETERS
+                .into_iter()
+                .filter(|name| {
+                    args.iter()
+                        .any(|pattern| glob_match(&pattern.to_ascii_lowercase(), name.as_bytes()))
+                })
+                .filter_map(|name| Some((name, config.get(name)?)))
+                .map(|(name, value)| {
+                    (
+                        RespMessage::BulkString(name.as_bytes().to_vec()),
+                        RespMessage::BulkString(value.into_bytes()),
+                    )
+                })
+                .collect();
+            RespMessage::Map(pairs)
+        }
+        (b"SET", [name, value]) => {
+            let name = String::from_utf8_lossy(name).to_lowercase();
+            if !PARAMETERS.contains(&name.as_str()) {
+                return error(&format!(
+                    "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                    name
+                ));
+            }
+
+            let value = String::from_utf8_lossy(value);
+            match shared.config.write().unwrap().set(&name, &value) {
+                Ok(()) => ok(),
+                Err(e) => error(&format!(
+                    "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+                    name, e
+                )),
+            }
+        }
+        (b"HELP", []) => help("CONFIG", CONFIG_HELP),
+        (b"GET" | b"SET", _) => wrong_arity(&format!(
+            "config|{}",
+            String::from_utf8_lossy(subcommand).to_lowercase()
+        )),
+        _ => unknown_subcommand("CONFIG", subcommand),
+    }
+}
+
+pub fn debug(shared: &Shared, args: &[Vec<u8>]) -> RespMessage {
+    let Some((subcommand, args)) = args.split_first() else {
+        return wrong_arity("debug");
+    };
+
+    match (subcommand.to_ascii_uppercase().as_slice(), args) {
+        (b"SLEEP", _) => match sleep_duration(args) {
+            Ok(_) => ok(),
+            Err(reply) => reply,
+        },
+        (b"SET-ACTIVE-EXPIRE", [enabled]) => match enabled.as_slice() {
+            b"0" | b"1" => {
+                shared.store.set_active_expire(enabled == b"1");
+                ok()
+            }
+            _ => error(NOT_AN_INTEGER),
+        },
+        (b"SET-ACTIVE-EXPIRE", _) => wrong_arity("debug|set-active-expire"),
+        (b"HELP", []) => help("DEBUG", DEBUG_HELP),
+        _ => unknown_subcommand("DEBUG", subcommand),
+    }
+}
+
+pub fn sleep_duration(args: &[Vec<u8>]) -> Result<Duration, RespMessage> {
+    let [seconds] = args else {
+        return Err(wrong_arity("debug|sleep"));
+    };
+
+    parse_float(seconds)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| error(NOT_A_FLOAT))
+}
+
+pub fn time() -> RespMessage {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    RespMessage::Array(vec![
+        RespMessage::BulkString(now.as_secs().to_string().into_bytes()),
+        RespMessage::BulkString(now.subsec_micros().to_string().into_bytes()),
+    ])
+}
+
+pub fn wait(args: &[Vec<u8>]) -> RespMessage {
+    let [numreplicas, timeout] = args else {
+        return wrong_arity("wait");
+    };
+    let (Some(_), Some(timeout)) = (parse_integer(numreplicas), parse_integer(timeout)) else {
+        return error(NOT_AN_INTEGER);
+    };
+    if timeout < 0 {
+        return error("ERR timeout is negative");
+    }
+
+    RespMessage::Integer(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    };
+
+    use crate::commands::{command, run};
+    use crate::config::Config;
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+    use crate::store::Store;
+
+    fn fields(reply: RespMessage) -> HashMap<String, String> {
+        let RespMessage::BulkString(report) = reply else {
+            panic!("expected a bulk string, got {:?}", reply);
+        };
+        String::from_utf8(report)
+            .unwrap()
+            .split("\r\n")
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (field, value) = line.split_once(':').unwrap();
+                (field.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_info_reports_fields() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "a", "1"]));
+        run(&shared, command(&["SETEX", "b", "100", "1"]));
+
+        let fields = fields(run(&shared, command(&["INFO"])));
+
+        for field in [
+            "redis_version",
+            "uptime_in_seconds",
+            "connected_clients",
+            "used_memory",
+            "total_commands_processed",
+        ] {
+            assert!(fields.contains_key(field), "missing {}", field);
+        }
+        assert_eq!(fields["db0"], "keys=2,expires=1,avg_ttl=0");
+    }
+
+    #[test]
+    fn test_info_section() {
+        let fields = fields(run(&Shared::default(), command(&["INFO", "clients"])));
+
+        assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["connected_clients"]);
+    }
+
+    #[test]
+    fn test_info_unknown_section() {
+        assert_eq!(
+            run(&Shared::default(), command(&["INFO", "nope"])),
+            RespMessage::BulkString(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_command_count() {
+        let RespMessage::Integer(count) = run(&Shared::default(), command(&["COMMAND", "COUNT"]))
+        else {
+            panic!("expected an integer");
+        };
+
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn test_command_docs() {
+        let RespMessage::Map(docs) = run(&Shared::default(), command(&["COMMAND", "DOCS"])) else {
+            panic!("expected a map");
+        };
+        let RespMessage::Integer(count) = run(&Shared::default(), command(&["COMMAND", "COUNT"]))
+        else {
+            panic!("expected an integer");
+        };
+        assert_eq!(docs.len() as i64, count);
+
+        assert_eq!(
+            run(
+                &Shared::default(),
+                command(&["COMMAND", "DOCS", "get", "nope"])
+            ),
+            RespMessage::Map(vec![(
+                RespMessage::BulkString(b"get".to_vec()),
+                RespMessage::Map(vec![
+                    (
+                        RespMessage::BulkString(b"arity".to_vec()),
+                        RespMessage::Integer(2)
+                    ),
+                    (
+                        RespMessage::BulkString(b"flags".to_vec()),
+                        RespMessage::Array(Vec::new())
+                    ),
+                ])
+            )])
+        );
+        assert!(matches!(
+            run(&Shared::default(), command(&["COMMAND", "NOPE"])),
+            RespMessage::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_config_get() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "GET", "maxclients"])),
+            RespMessage::Map(vec![(
+                RespMessage::BulkString(b"maxclients".to_vec()),
+                RespMessage::BulkString(b"10000".to_vec()),
+            )])
+        );
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "GET", "append*"])),
+            RespMessage::Map(
+                [
+                    ("appendonly", "no"),
+                    ("appendfilename", "appendonly.aof"),
+                    ("appendfsync", "everysec")
+                ]
+                .into_iter()
+                .map(|(name, value)| (
+                    RespMessage::BulkString(name.as_bytes().to_vec()),
+                    RespMessage::BulkString(value.as_bytes().to_vec())
+                ))
+                .collect()
+            )
+        );
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "GET", "nope"])),
+            RespMessage::Map(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_subcommand_help() {
+        let shared = Shared::default();
+
+        for name in ["CLIENT", "CONFIG", "COMMAND", "DEBUG", "PUBSUB"] {
+            let RespMessage::Array(lines) = run(&shared, command(&[name, "help"])) else {
+                panic!("expected an array for {}", name);
+            };
+            assert_eq!(
+                lines.first(),
+                Some(&RespMessage::SimpleString(format!(
+                    "{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+                    name
+                )))
+            );
+        }
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "NOPE"])),
+            RespMessage::Error("ERR unknown subcommand 'NOPE'. Try CONFIG HELP.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_config_set() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "SET", "maxclients", "100"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["CONFIG", "GET", "maxclients"])),
+            RespMessage::Map(vec![(
+                RespMessage::BulkString(b"maxclients".to_vec()),
+                RespMessage::BulkString(b"100".to_vec()),
+            )])
+        );
+        assert_eq!(shared.config().max_clients, 100);
+        assert!(matches!(
+            run(&shared, command(&["CONFIG", "SET", "nope", "1"])),
+            RespMessage::Error(_)
+        ));
+        assert!(matches!(
+            run(&shared, command(&["CONFIG", "SET", "maxclients", "many"])),
+            RespMessage::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_debug_set_active_expire() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "SET-ACTIVE-EXPIRE", "0"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "set-active-expire", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "SET-ACTIVE-EXPIRE", "2"])),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_debug_errors() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "JMAP"])),
+            RespMessage::Error("ERR unknown subcommand 'JMAP'. Try DEBUG HELP.".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "SLEEP", "soon"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["DEBUG", "SLEEP", "-1"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+    }
+
+    #[test]
+    fn test_time() {
+        let RespMessage::Array(parts) = run(&Shared::default(), command(&["TIME"])) else {
+            panic!("expected an array");
+        };
+        let [
+            RespMessage::BulkString(seconds),
+            RespMessage::BulkString(micros),
+        ] = &parts[..]
+        else {
+            panic!("expected two bulk strings");
+        };
+
+        let seconds: u64 = String::from_utf8_lossy(seconds).parse().unwrap();
+        let micros: u32 = String::from_utf8_lossy(micros).parse().unwrap();
+        assert!(seconds > 1_600_000_000);
+        assert!(micros < 1_000_000);
+    }
+
+    #[test]
+    fn test_wait_without_replicas() {
+        let started = Instant::now();
+
+        assert_eq!(
+            run(&Shared::default(), command(&["WAIT", "0", "100"])),
+            RespMessage::Integer(0)
+        );
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert_eq!(
+            run(&Shared::default(), command(&["WAIT", "0", "-1"])),
+            RespMessage::Error("ERR timeout is negative".to_string())
+        );
+    }
+
+    #[test]
+    fn test_save_writes_snapshot() {
+        let path =
+            std::env::temp_dir().join(format!("redis-clone-save-{}.rdb", std::process::id()));
+        let shared = Shared {
+            config: Arc::new(RwLock::new(Config {
+                dbfilename: path.clone(),
+                ..Config::default()
+            })),
+            ..Shared::default()
+        };
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["SAVE"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        let restored = Store::default();
+        crate::rdb::load(&restored, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(restored.lock(0).exists(b"foo"));
+    }
+}
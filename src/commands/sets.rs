@@ -0,0 +1,637 @@
+use rand::seq::{IndexedRandom, IteratorRandom};
+
+use crate::{
+    commands::{NOT_AN_INTEGER, WRONG_TYPE, error, parse_integer, wrong_arity},
+    parser::{DEFAULT_MAX_FRAME_ELEMENTS, RespMessage},
+    server::Shared,
+    store::{Keyspace, Members, Value},
+};
+
+const NOT_POSITIVE: &str = "ERR value is out of range, must be positive";
+const OUT_OF_RANGE: &str = "ERR value is out of range";
+
+#[derive(Clone, Copy)]
+pub enum Operation {
+    Intersection,
+    Union,
+    Difference,
+}
+
+pub fn sadd(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, members @ ..] = args else {
+        return wrong_arity("sadd");
+    };
+    let Value::Set(set) = keyspace.get_or_insert_with(key, || Value::Set(Members::default()))
+    else {
+        return error(WRONG_TYPE);
+    };
+
+    let added = members
+        .iter()
+        .filter(|member| set.insert(member.to_vec()))
+        .count();
+    RespMessage::Integer(added as i64)
+}
+
+pub fn srem(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, members @ ..] = args else {
+        return wrong_arity("srem");
+    };
+    let (removed, now_empty) = match keyspace.get_mut(key) {
+        Some(Value::Set(set)) => {
+            let removed = members.iter().filter(|member| set.remove(*member)).count();
+            (removed, set.is_empty())
+        }
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Integer(0),
+    };
+    if now_empty {
+        keyspace.remove(key);
+    }
+
+    RespMessage::Integer(removed as i64)
+}
+
+pub fn sismember(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, member] = args else {
+        return wrong_arity("sismember");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Set(set)) => RespMessage::Integer(set.contains(member) as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn smembers(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("smembers");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Set(set)) => {
+            RespMessage::Array(set.iter().cloned().map(RespMessage::BulkString).collect())
+        }
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Array(vec![]),
+    }
+}
+
+pub fn scard(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("scard");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::Set(set)) => RespMessage::Integer(set.len() as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn spop(shared: &Shared, keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let (key, count) = match args {
+        [key] => (key, None),
+        [key, count] => match parse_integer(count) {
+            Some(count) if count >= 0 => (key, Some(count as usize)),
+            _ => return error(NOT_POSITIVE),
+        },
+        _ => return wrong_arity("spop"),
+    };
+
+    let (popped, now_empty) = match keyspace.get_mut(key) {
+        Some(Value::Set(set)) => {
+            let amount = count.unwrap_or(1).min(set.len());
+            let popped: Vec<Vec<u8>> = set
+                .iter()
+                .choose_multiple(&mut *shared.rng.lock().unwrap(), amount)
+                .into_iter()
+                .cloned()
+                .collect();
+            for member in &popped {
+                set.remove(member);
+            }
+            (popped, set.is_empty())
+        }
+        Some(_) => return error(WRONG_TYPE),
+        None if count.is_some() => return RespMessage::Array(vec![]),
+        None => return RespMessage::Null,
+    };
+    if now_empty {
+        keyspace.remove(key);
+    }
+
+    match count {
+        Some(_) => RespMessage::Array(popped.into_iter().map(RespMessage::BulkString).collect()),
+        None => popped
+            .into_iter()
+            .next()
+            .map_or(RespMessage::Null, RespMessage::BulkString),
+    }
+}
+
+pub fn srandmember(shared: &Shared, keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let (key, count) = match args {
+        [key] => (key, None),
+        [key, count] => match parse_integer(count) {
+            Some(count) if count < -(DEFAULT_MAX_FRAME_ELEMENTS as i64) => {
+                return error(OUT_OF_RANGE);
+            }
+            Some(count) => (key, Some(count)),
+            None => return error(NOT_AN_INTEGER),
+        },
+        _ => return wrong_arity("srandmember"),
+    };
+
+    let set = match keyspace.get(key) {
+        Some(Value::Set(set)) => set,
+        Some(_) => return error(WRONG_TYPE),
+        None if count.is_some() => return RespMessage::Array(vec![]),
+        None => return RespMessage::Null,
+    };
+
+    let rng = &mut *shared.rng.lock().unwrap();
+    let chosen: Vec<&Vec<u8>> = match count {
+        None => set.iter().choose(rng).into_iter().collect(),
+        Some(count) if count >= 0 => set
+            .iter()
+            .choose_multiple(rng, (count as usize).min(set.len())),
+        Some(count) => {
+            let members: Vec<&Vec<u8>> = set.iter().collect();
+            (0..count.unsigned_abs())
+                .filter_map(|_| members.choose(rng).copied())
+                .collect()
+        }
+    };
+
+    let mut chosen = chosen
+        .into_iter()
+        .map(|member| RespMessage::BulkString(member.to_vec()));
+    match count {
+        Some(_) => RespMessage::Array(chosen.collect()),
+        None => chosen.next().unwrap_or(RespMessage::Null),
+    }
+}
+
+pub fn combine(keyspace: &mut Keyspace, args: &[Vec<u8>], operation: Operation) -> RespMessage {
+    match apply(keyspace, args, operation) {
+        Ok(result) => RespMessage::Array(result.into_iter().map(RespMessage::BulkString).collect()),
+        Err(reply) => reply,
+    }
+}
+
+pub fn combine_store(
+    keyspace: &mut Keyspace,
+    args: &[Vec<u8>],
+    operation: Operation,
+    name: &str,
+) -> RespMessage {
+    let [destination, keys @ ..] = args else {
+        return wrong_arity(name);
+    };
+    let result = match apply(keyspace, keys, operation) {
+        Ok(result) => result,
+        Err(reply) => return reply,
+    };
+    let cardinality = result.len() as i64;
+    if result.is_empty() {
+        keyspace.remove(destination);
+    } else {
+        keyspace.set(destination.clone(), Value::Set(result));
+    }
+
+    RespMessage::Integer(cardinality)
+}
+
+fn apply(
+    keyspace: &mut Keyspace,
+    keys: &[Vec<u8>],
+    operation: Operation,
+) -> Result<Members, RespMessage> {
+    let mut result: Option<Members> = None;
+    for key in keys {
+        let set = match keyspace.get(key) {
+            Some(Value::Set(set)) => Some(set),
+            Some(_) => return Err(error(WRONG_TYPE)),
+            None => None,
+        };
+
+        result = Some(match (result, operation) {
+            (None, _) => set.cloned().unwrap_or_default(),
+            (Some(mut acc), Operation::Intersection) => {
+                acc.retain(|member| set.is_some_and(|set| set.contains(member)));
+                acc
+            }
+            (Some(mut acc), Operation::Union) => {
+                acc.extend(set.into_iter().flatten().cloned());
+                acc
+            }
+            (Some(mut acc), Operation::Difference) => {
+                acc.retain(|member| !set.is_some_and(|set| set.contains(member)));
+                acc
+            }
+        });
+    }
+
+    Ok(result.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    fn sorted_members(reply: RespMessage) -> Vec<RespMessage> {
+        let RespMessage::Array(mut members) = reply else {
+            panic!("expected an array, got {:?}", reply);
+        };
+        members.sort_by_key(|member| match member {
+            RespMessage::BulkString(bytes) => bytes.clone(),
+            _ => Vec::new(),
+        });
+        members
+    }
+
+    #[test]
+    fn test_sadd_ignores_duplicates() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SADD", "set", "a", "b", "a"])),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            run(&shared, command(&["SADD", "set", "b", "c"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            sorted_members(run(&shared, command(&["SMEMBERS", "set"]))),
+            vec![
+                RespMessage::BulkString(b"a".to_vec()),
+                RespMessage::BulkString(b"b".to_vec()),
+                RespMessage::BulkString(b"c".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sismember() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a"]));
+
+        assert_eq!(
+            run(&shared, command(&["SISMEMBER", "set", "a"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["SISMEMBER", "set", "b"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["SISMEMBER", "missing", "a"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_srem_deletes_empty_set() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a", "b"]));
+
+        assert_eq!(
+            run(&shared, command(&["SREM", "set", "a", "missing"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["SREM", "set", "b"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "set"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["SMEMBERS", "set"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_scard() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a", "b", "c"]));
+
+        assert_eq!(
+            run(&shared, command(&["SCARD", "set"])),
+            RespMessage::Integer(3)
+        );
+        assert_eq!(
+            run(&shared, command(&["SCARD", "missing"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_spop_until_empty() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a", "b", "c"]));
+
+        let RespMessage::BulkString(first) = run(&shared, command(&["SPOP", "set"])) else {
+            panic!("expected a member");
+        };
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SISMEMBER", "set", &String::from_utf8(first).unwrap()])
+            ),
+            RespMessage::Integer(0)
+        );
+        let RespMessage::Array(rest) = run(&shared, command(&["SPOP", "set", "5"])) else {
+            panic!("expected an array");
+        };
+        assert_eq!(rest.len(), 2);
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "set"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(run(&shared, command(&["SPOP", "set"])), RespMessage::Null);
+        assert_eq!(
+            run(&shared, command(&["SPOP", "set", "1"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_spop_negative_count() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a"]));
+
+        assert_eq!(
+            run(&shared, command(&["SPOP", "set", "-1"])),
+            RespMessage::Error("ERR value is out of range, must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_srandmember_does_not_remove() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a", "b", "c"]));
+
+        assert_eq!(
+            sorted_members(run(&shared, command(&["SRANDMEMBER", "set", "10"]))),
+            vec![
+                RespMessage::BulkString(b"a".to_vec()),
+                RespMessage::BulkString(b"b".to_vec()),
+                RespMessage::BulkString(b"c".to_vec()),
+            ]
+        );
+        assert!(matches!(
+            run(&shared, command(&["SRANDMEMBER", "set"])),
+            RespMessage::BulkString(_)
+        ));
+        assert_eq!(
+            run(&shared, command(&["SCARD", "set"])),
+            RespMessage::Integer(3)
+        );
+    }
+
+    #[test]
+    fn test_srandmember_negative_count_allows_repeats() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a"]));
+
+        assert_eq!(
+            run(&shared, command(&["SRANDMEMBER", "set", "-3"])),
+            RespMessage::Array(vec![RespMessage::BulkString(b"a".to_vec()); 3])
+        );
+        assert_eq!(
+            run(&shared, command(&["SRANDMEMBER", "missing", "-3"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_random_member_counts_are_bounded() {
+        let shared = Shared::default();
+        run(&shared, command(&["SADD", "set", "a", "b"]));
+
+        for count in ["-9223372036854775808", "-9223372036854775807"] {
+            assert_eq!(
+                run(&shared, command(&["SRANDMEMBER", "set", count])),
+                RespMessage::Error("ERR value is out of range".to_string())
+            );
+        }
+        let RespMessage::Array(members) = run(
+            &shared,
+            command(&["SRANDMEMBER", "set", "9223372036854775807"]),
+        ) else {
+            panic!("expected an array");
+        };
+        assert_eq!(members.len(), 2);
+        let RespMessage::Array(popped) =
+            run(&shared, command(&["SPOP", "set", "9223372036854775807"]))
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(popped.len(), 2);
+    }
+
+    #[test]
+    fn test_spop_is_reproducible_with_seed() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let pop = || {
+            let shared = Shared::default();
+            *shared.rng.lock().unwrap() = StdRng::seed_from_u64(7);
+            run(&shared, command(&["SADD", "set", "a", "b", "c", "d", "e"]));
+            [
+                run(&shared, command(&["SPOP", "set"])),
+                run(&shared, command(&["SPOP", "set", "2"])),
+            ]
+        };
+
+        assert_eq!(pop(), pop());
+    }
+
+    #[test]
+    fn test_srandmember_is_reproducible_with_seed() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let pick = || {
+            let shared = Shared::default();
+            *shared.rng.lock().unwrap() = StdRng::seed_from_u64(7);
+            run(&shared, command(&["SADD", "set", "a", "b", "c", "d", "e"]));
+            run(&shared, command(&["SRANDMEMBER", "set", "-10"]))
+        };
+
+        assert_eq!(pick(), pick());
+    }
+
+    fn members(items: &[&str]) -> Vec<RespMessage> {
+        items
+            .iter()
+            .map(|item| RespMessage::BulkString(item.as_bytes().to_vec()))
+            .collect()
+    }
+
+    fn populate(shared: &Shared) {
+        run(shared, command(&["SADD", "first", "a", "b", "c", "d"]));
+        run(shared, command(&["SADD", "second", "c", "d", "e"]));
+        run(shared, command(&["SADD", "third", "a", "d"]));
+    }
+
+    #[test]
+    fn test_sinter() {
+        let shared = Shared::default();
+        populate(&shared);
+
+        assert_eq!(
+            sorted_members(run(
+                &shared,
+                command(&["SINTER", "first", "second", "third"])
+            )),
+            members(&["d"])
+        );
+        assert_eq!(
+            run(&shared, command(&["SINTER", "first", "missing"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_sunion() {
+        let shared = Shared::default();
+        populate(&shared);
+
+        assert_eq!(
+            sorted_members(run(
+                &shared,
+                command(&["SUNION", "second", "missing", "third"])
+            )),
+            members(&["a", "c", "d", "e"])
+        );
+    }
+
+    #[test]
+    fn test_sdiff() {
+        let shared = Shared::default();
+        populate(&shared);
+
+        assert_eq!(
+            sorted_members(run(
+                &shared,
+                command(&["SDIFF", "first", "second", "missing"])
+            )),
+            members(&["a", "b"])
+        );
+        assert_eq!(
+            run(&shared, command(&["SDIFF", "missing", "first"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_store_variants() {
+        let shared = Shared::default();
+        populate(&shared);
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SINTERSTORE", "result", "first", "second"])
+            ),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            sorted_members(run(&shared, command(&["SMEMBERS", "result"]))),
+            members(&["c", "d"])
+        );
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SUNIONSTORE", "result", "second", "third"])
+            ),
+            RespMessage::Integer(4)
+        );
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SDIFFSTORE", "first", "first", "second"])
+            ),
+            RespMessage::Integer(2)
+        );
+        assert_eq!(
+            sorted_members(run(&shared, command(&["SMEMBERS", "first"]))),
+            members(&["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_store_empty_result_removes_destination() {
+        let shared = Shared::default();
+        populate(&shared);
+        run(&shared, command(&["SET", "result", "x"]));
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SINTERSTORE", "result", "first", "missing"])
+            ),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "result"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_set_algebra_wrong_type() {
+        let shared = Shared::default();
+        populate(&shared);
+        run(&shared, command(&["SET", "string", "x"]));
+
+        for cmd in [
+            &["SINTER", "missing", "string"][..],
+            &["SUNION", "first", "string"],
+            &["SDIFF", "first", "string"],
+            &["SDIFFSTORE", "result", "first", "string"],
+        ] {
+            assert_eq!(
+                run(&shared, command(cmd)),
+                RespMessage::Error(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+                )
+            );
+        }
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "result"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_set_commands_on_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "string", "x"]));
+
+        for cmd in [
+            &["SADD", "string", "a"][..],
+            &["SREM", "string", "a"],
+            &["SISMEMBER", "string", "a"],
+            &["SMEMBERS", "string"],
+            &["SCARD", "string"],
+            &["SPOP", "string"],
+            &["SRANDMEMBER", "string"],
+        ] {
+            assert_eq!(
+                run(&shared, command(cmd)),
+                RespMessage::Error(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,842 @@
+use std::time::Duration;
+
+use crate::{
+    commands::{
+        NOT_A_FLOAT, NOT_AN_INTEGER, SYNTAX_ERROR, WRONG_TYPE, error, ok, parse_float,
+        parse_integer, ttl_millis, unix_millis, wrong_arity,
+    },
+    parser::{DEFAULT_MAX_BULK_LEN, RespMessage},
+    store::{Keyspace, Value},
+};
+
+pub fn get(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("get");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::String(value)) => RespMessage::BulkString(value.clone()),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn getdel(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("getdel");
+    };
+
+    let value = match keyspace.get(key) {
+        Some(Value::String(value)) => value.clone(),
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Null,
+    };
+    keyspace.remove(key);
+
+    RespMessage::BulkString(value)
+}
+
+#[derive(PartialEq)]
+enum Condition {
+    Always,
+    IfMissing,
+    IfExists,
+}
+
+pub fn set(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, value, options @ ..] = args else {
+        return wrong_arity("set");
+    };
+
+    let mut condition = Condition::Always;
+    let mut ttl = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let option = option.to_ascii_uppercase();
+        match option.as_slice() {
+            b"NX" | b"XX" => {
+                if condition != Condition::Always {
+                    return error(SYNTAX_ERROR);
+                }
+                condition = if option == b"NX" {
+                    Condition::IfMissing
+                } else {
+                    Condition::IfExists
+                };
+            }
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" => {
+                let Some(amount) = options.next() else {
+                    return error(SYNTAX_ERROR);
+                };
+                if ttl.is_some() {
+                    return error(SYNTAX_ERROR);
+                }
+                let Some(amount) = parse_integer(amount) else {
+                    return error(NOT_AN_INTEGER);
+                };
+                let unit = if option.starts_with(b"EX") {
+                    Duration::from_secs(1)
+                } else {
+                    Duration::from_millis(1)
+                };
+                let millis = if option.ends_with(b"AT") {
+                    amount
+                        .checked_mul(unit.as_millis() as i64)
+                        .filter(|deadline| *deadline > 0)
+                        .map(|deadline| deadline.saturating_sub(unix_millis()).max(0))
+                } else {
+                    ttl_millis(amount, unit).filter(|millis| *millis > 0)
+                };
+                let Some(millis) = millis else {
+                    return error("ERR invalid expire time in 'set' command");
+                };
+                ttl = Some(Duration::from_millis(millis as u64));
+            }
+            _ => return error(SYNTAX_ERROR),
+        }
+    }
+
+    let exists = keyspace.exists(key);
+    match condition {
+        Condition::IfMissing if exists => return RespMessage::Null,
+        Condition::IfExists if !exists => return RespMessage::Null,
+        _ => {}
+    }
+
+    keyspace.set(key.clone(), Value::String(value.clone()));
+    if let Some(ttl) = ttl {
+        keyspace.expire(key, ttl);
+    }
+    ok()
+}
+
+pub fn mget(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    RespMessage::Array(
+        args.iter()
+            .map(|key| match keyspace.get(key) {
+                Some(Value::String(value)) => RespMessage::BulkString(value.clone()),
+                _ => RespMessage::Null,
+            })
+            .collect(),
+    )
+}
+
+pub fn mset(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return wrong_arity("mset");
+    }
+
+    for pair in args.chunks(2) {
+        keyspace.set(pair[0].clone(), Value::String(pair[1].clone()));
+    }
+    ok()
+}
+
+pub fn setex(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, seconds, value] = args else {
+        return wrong_arity("setex");
+    };
+    let Some(seconds) = parse_integer(seconds) else {
+        return error(NOT_AN_INTEGER);
+    };
+    let ttl = match ttl_millis(seconds, Duration::from_secs(1)) {
+        Some(millis) if millis > 0 => Duration::from_millis(millis as u64),
+        _ => return error("ERR invalid expire time in 'setex' command"),
+    };
+
+    keyspace.set(key.clone(), Value::String(value.clone()));
+    keyspace.expire(key, ttl);
+    ok()
+}
+
+pub fn append(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, suffix] = args else {
+        return wrong_arity("append");
+    };
+
+    let Value::String(value) = keyspace.get_or_insert_with(key, || Value::String(Vec::new()))
+    else {
+        return error(WRONG_TYPE);
+    };
+    value.extend_from_slice(suffix);
+
+    RespMessage::Integer(value.len() as i64)
+}
+
+pub fn strlen(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity("strlen");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::String(value)) => RespMessage::Integer(value.len() as i64),
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Integer(0),
+    }
+}
+
+pub fn getrange(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, start, end] = args else {
+        return wrong_arity("getrange");
+    };
+    let (Some(start), Some(end)) = (parse_integer(start), parse_integer(end)) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let value = match keyspace.get(key) {
+        Some(Value::String(value)) => value,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::BulkString(Vec::new()),
+    };
+
+    let len = value.len() as i64;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.max(0).min(len - 1);
+    if len == 0 || start > end {
+        return RespMessage::BulkString(Vec::new());
+    }
+
+    RespMessage::BulkString(value[start as usize..=end as usize].to_vec())
+}
+
+pub fn setrange(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, offset, patch] = args else {
+        return wrong_arity("setrange");
+    };
+    let Some(offset) = parse_integer(offset) else {
+        return error(NOT_AN_INTEGER);
+    };
+    if offset < 0 {
+        return error("ERR offset is out of range");
+    }
+    let offset = offset as usize;
+    if offset + patch.len() > DEFAULT_MAX_BULK_LEN {
+        return error("ERR string exceeds maximum allowed size (proto-max-bulk-len)");
+    }
+
+    if patch.is_empty() {
+        return match keyspace.get(key) {
+            Some(Value::String(value)) => RespMessage::Integer(value.len() as i64),
+            Some(_) => error(WRONG_TYPE),
+            None => RespMessage::Integer(0),
+        };
+    }
+
+    let Value::String(value) = keyspace.get_or_insert_with(key, || Value::String(Vec::new()))
+    else {
+        return error(WRONG_TYPE);
+    };
+    if value.len() < offset + patch.len() {
+        value.resize(offset + patch.len(), 0);
+    }
+    value[offset..offset + patch.len()].copy_from_slice(patch);
+
+    RespMessage::Integer(value.len() as i64)
+}
+
+pub fn incr_by(keyspace: &mut Keyspace, args: &[Vec<u8>], delta: i64, name: &str) -> RespMessage {
+    let [key] = args else {
+        return wrong_arity(name);
+    };
+
+    increment(keyspace, key, delta)
+}
+
+pub fn incrby(keyspace: &mut Keyspace, args: &[Vec<u8>], sign: i64, name: &str) -> RespMessage {
+    let [key, delta] = args else {
+        return wrong_arity(name);
+    };
+    let Some(delta) = parse_integer(delta) else {
+        return error(NOT_AN_INTEGER);
+    };
+    let Some(delta) = delta.checked_mul(sign) else {
+        return error("ERR decrement would overflow");
+    };
+
+    increment(keyspace, key, delta)
+}
+
+fn increment(keyspace: &mut Keyspace, key: &[u8], delta: i64) -> RespMessage {
+    let current = match keyspace.get(key) {
+        Some(Value::String(value)) => parse_integer(value),
+        Some(_) => return error(WRONG_TYPE),
+        None => Some(0),
+    };
+    let Some(updated) = current.and_then(|n| n.checked_add(delta)) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    store_string(keyspace, key, updated.to_string().into_bytes());
+    RespMessage::Integer(updated)
+}
+
+pub fn incrbyfloat(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, delta] = args else {
+        return wrong_arity("incrbyfloat");
+    };
+    let Some(delta) = parse_float(delta) else {
+        return error(NOT_A_FLOAT);
+    };
+
+    let current = match keyspace.get(key) {
+        Some(Value::String(value)) => parse_float(value),
+        Some(_) => return error(WRONG_TYPE),
+        None => Some(0.0),
+    };
+    let Some(current) = current else {
+        return error(NOT_A_FLOAT);
+    };
+    let updated = current + delta;
+    if !updated.is_finite() {
+        return error("ERR increment would produce NaN or Infinity");
+    }
+
+    let encoded = updated.to_string().into_bytes();
+    store_string(keyspace, key, encoded.clone());
+    RespMessage::BulkString(encoded)
+}
+
+fn store_string(keyspace: &mut Keyspace, key: &[u8], encoded: Vec<u8>) {
+    match keyspace.get_mut(key) {
+        Some(value) => *value = Value::String(encoded),
+        None => keyspace.set(key.to_vec(), Value::String(encoded)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    #[test]
+    fn test_get_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["GET", "foo"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_set_then_get() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "bar"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_getdel_existing_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["GETDEL", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "foo"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_getdel_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["GETDEL", "foo"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_getdel_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["GETDEL", "list"])),
+            RespMessage::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "list"])),
+            RespMessage::Integer(1)
+        );
+    }
+
+    #[test]
+    fn test_mget() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "a", "1"]));
+        run(&shared, command(&["SET", "c", "3"]));
+        run(&shared, command(&["RPUSH", "list", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["MGET", "a", "missing", "list", "c"])),
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"1".to_vec()),
+                RespMessage::Null,
+                RespMessage::Null,
+                RespMessage::BulkString(b"3".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_mset() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "b", "x"]));
+
+        assert_eq!(
+            run(&shared, command(&["MSET", "a", "1", "b", "2"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["MGET", "a", "b"])),
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"1".to_vec()),
+                RespMessage::BulkString(b"2".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_mset_odd_arguments() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["MSET", "a", "1", "b"])),
+            RespMessage::Error("ERR wrong number of arguments for 'mset' command".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "a"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_incr_missing_key() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["INCR", "counter"])),
+            RespMessage::Integer(1)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "counter"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_decr_existing_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "counter", "10"]));
+
+        assert_eq!(
+            run(&shared, command(&["DECR", "counter"])),
+            RespMessage::Integer(9)
+        );
+    }
+
+    #[test]
+    fn test_incr_non_integer() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["INCR", "foo"])),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_incr_overflow() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "counter", &i64::MAX.to_string()]));
+
+        assert_eq!(
+            run(&shared, command(&["INCR", "counter"])),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "counter"])),
+            RespMessage::BulkString(i64::MAX.to_string().into_bytes())
+        );
+    }
+
+    #[test]
+    fn test_incrby_and_decrby() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["INCRBY", "counter", "10"])),
+            RespMessage::Integer(10)
+        );
+        assert_eq!(
+            run(&shared, command(&["DECRBY", "counter", "3"])),
+            RespMessage::Integer(7)
+        );
+        assert_eq!(
+            run(&shared, command(&["INCRBY", "counter", "-10"])),
+            RespMessage::Integer(-3)
+        );
+    }
+
+    #[test]
+    fn test_incrby_invalid_delta() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["INCRBY", "counter", "1.5"])),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+        assert_eq!(
+            run(
+                &shared,
+                command(&["DECRBY", "counter", &i64::MIN.to_string()])
+            ),
+            RespMessage::Error("ERR decrement would overflow".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "counter"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_incrby_overflow() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "counter", "1"]));
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["INCRBY", "counter", &i64::MAX.to_string()])
+            ),
+            RespMessage::Error("ERR value is not an integer or out of range".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "counter"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_incrbyfloat() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "price", "10.50"]));
+
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "price", "0.1"])),
+            RespMessage::BulkString(b"10.6".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "price", "-5.6"])),
+            RespMessage::BulkString(b"5".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "other", "5.0e3"])),
+            RespMessage::BulkString(b"5000".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "price"])),
+            RespMessage::BulkString(b"5".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_incrbyfloat_invalid() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar"]));
+
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "foo", "1"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "counter", "abc"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["INCRBYFLOAT", "counter", "inf"])),
+            RespMessage::Error("ERR increment would produce NaN or Infinity".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_ex() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "bar", "EX", "100"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(100)
+        );
+    }
+
+    #[test]
+    fn test_set_ex_overflow() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SET", "foo", "bar", "EX", "9223372036854775807"])
+            ),
+            RespMessage::Error("ERR invalid expire time in 'set' command".to_string())
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+    }
+
+    #[test]
+    fn test_set_exat_and_pxat() {
+        let shared = Shared::default();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap();
+        let exat = (now.as_secs() + 100).to_string();
+        let pxat = (now.as_millis() + 100_000).to_string();
+
+        run(&shared, command(&["SET", "seconds", "x", "EXAT", &exat]));
+        run(&shared, command(&["SET", "millis", "x", "pxat", &pxat]));
+        for key in ["seconds", "millis"] {
+            assert!(matches!(
+                run(&shared, command(&["TTL", key])),
+                RespMessage::Integer(ttl) if (99..=100).contains(&ttl)
+            ));
+        }
+        run(&shared, command(&["SET", "past", "x", "PXAT", "1"]));
+        assert_eq!(run(&shared, command(&["GET", "past"])), RespMessage::Null);
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "x", "PXAT", "0"])),
+            RespMessage::Error("ERR invalid expire time in 'set' command".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_px() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "bar", "px", "20"]));
+
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(0)
+        );
+        std::thread::sleep(std::time::Duration::from_millis(40));
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+    }
+
+    #[test]
+    fn test_set_nx() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "1", "NX"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "2", "NX"])),
+            RespMessage::Null
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"1".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_set_xx() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "1", "XX"])),
+            RespMessage::Null
+        );
+        run(&shared, command(&["SET", "foo", "1"]));
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "2", "XX"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"2".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_set_conflicting_options() {
+        let shared = Shared::default();
+
+        for cmd in [
+            &["SET", "foo", "1", "NX", "XX"][..],
+            &["SET", "foo", "1", "EX", "1", "PX", "1000"],
+            &["SET", "foo", "1", "EX"],
+            &["SET", "foo", "1", "BOGUS"],
+        ] {
+            assert_eq!(
+                run(&shared, command(cmd)),
+                RespMessage::Error("ERR syntax error".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_setex() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SETEX", "foo", "50", "bar"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"bar".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["TTL", "foo"])),
+            RespMessage::Integer(50)
+        );
+    }
+
+    #[test]
+    fn test_setex_overflow() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["SETEX", "foo", "9223372036854775807", "bar"])
+            ),
+            RespMessage::Error("ERR invalid expire time in 'setex' command".to_string())
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+    }
+
+    #[test]
+    fn test_append_to_new_key() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["APPEND", "foo", "hello"])),
+            RespMessage::Integer(5)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"hello".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_append_to_existing_key() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "hello"]));
+
+        assert_eq!(
+            run(&shared, command(&["APPEND", "foo", " world"])),
+            RespMessage::Integer(11)
+        );
+        assert_eq!(
+            run(&shared, command(&["STRLEN", "foo"])),
+            RespMessage::Integer(11)
+        );
+    }
+
+    #[test]
+    fn test_strlen_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["STRLEN", "foo"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_strlen_and_append_wrong_type() {
+        let shared = Shared::default();
+        run(&shared, command(&["RPUSH", "list", "a"]));
+
+        let wrong_type = RespMessage::Error(
+            "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+        );
+        assert_eq!(run(&shared, command(&["STRLEN", "list"])), wrong_type);
+        assert_eq!(run(&shared, command(&["APPEND", "list", "b"])), wrong_type);
+    }
+
+    #[test]
+    fn test_getrange() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "This is a string"]));
+
+        for (start, end, expected) in [
+            ("0", "3", "This"),
+            ("-3", "-1", "ing"),
+            ("0", "-1", "This is a string"),
+            ("10", "100", "string"),
+            ("5", "2", ""),
+            ("-100", "-100", "T"),
+        ] {
+            assert_eq!(
+                run(&shared, command(&["GETRANGE", "foo", start, end])),
+                RespMessage::BulkString(expected.as_bytes().to_vec()),
+                "GETRANGE {} {}",
+                start,
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn test_getrange_missing_key() {
+        assert_eq!(
+            run(&Shared::default(), command(&["GETRANGE", "foo", "0", "-1"])),
+            RespMessage::BulkString(vec![])
+        );
+    }
+
+    #[test]
+    fn test_setrange_overwrites() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "foo", "Hello World"]));
+
+        assert_eq!(
+            run(&shared, command(&["SETRANGE", "foo", "6", "Redis"])),
+            RespMessage::Integer(11)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"Hello Redis".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_setrange_zero_pads() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["SETRANGE", "foo", "3", "ab"])),
+            RespMessage::Integer(5)
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"\0\0\0ab".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_setrange_negative_offset() {
+        assert_eq!(
+            run(&Shared::default(), command(&["SETRANGE", "foo", "-1", "a"])),
+            RespMessage::Error("ERR offset is out of range".to_string())
+        );
+    }
+}
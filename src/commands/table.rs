@@ -0,0 +1,687 @@
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+use crate::{
+    commands::{
+        connection, echo, error, hashes, keys, lists, ping, publish, pubsub, server, sets, strings,
+        transactions, zsets,
+    },
+    connection::Client,
+    parser::RespMessage,
+    server::Shared,
+    store::{Keyspace, Store},
+};
+
+pub const WRITE: u8 = 1 << 0;
+pub const DENY_OOM: u8 = 1 << 1;
+pub const NO_AUTH: u8 = 1 << 2;
+pub const PUBSUB: u8 = 1 << 3;
+pub const BLOCKING: u8 = 1 << 4;
+
+const FLAG_NAMES: &[(u8, &str)] = &[
+    (WRITE, "write"),
+    (DENY_OOM, "denyoom"),
+    (NO_AUTH, "no-auth"),
+    (PUBSUB, "pubsub"),
+    (BLOCKING, "blocking"),
+];
+
+pub type KeyspaceHandler = fn(&Shared, &mut Keyspace<'_>, &[Vec<u8>]) -> RespMessage;
+pub type ServerHandler = fn(&Shared, &mut Client, &[Vec<u8>]) -> RespMessage;
+pub type BlockHandler = fn(&[Vec<u8>]) -> Option<Block>;
+
+#[derive(Clone, Copy)]
+pub enum Handler {
+    Keyspace(KeyspaceHandler),
+    Server(ServerHandler),
+    Connection,
+}
+
+pub enum Block {
+    Pop(Duration),
+    Sleep(Duration),
+}
+
+#[derive(Clone, Copy)]
+pub enum Keys {
+    Empty,
+    Leading(usize),
+    All,
+    AllButLast,
+    Pairs,
+    AfterSubcommand,
+    Cursor,
+    Database,
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub arity: i64,
+    pub flags: u8,
+    pub keys: Keys,
+    pub handler: Handler,
+    pub block: Option<BlockHandler>,
+}
+
+impl Command {
+    pub fn has_valid_arity(&self, argc: usize) -> bool {
+        let argc = argc as i64;
+        if self.arity >= 0 {
+            argc == self.arity
+        } else {
+            argc >= -self.arity
+        }
+    }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| self.has_flag(*flag))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    pub fn keys<'a>(&self, args: &'a [Vec<u8>]) -> Option<Vec<&'a [u8]>> {
+        let keys = match self.keys {
+            Keys::Empty => &args[..0],
+            Keys::Leading(count) => &args[..count.min(args.len())],
+            Keys::All => args,
+            Keys::AllButLast => &args[..args.len().saturating_sub(1)],
+            Keys::Pairs => return Some(args.iter().step_by(2).map(Vec::as_slice).collect()),
+            Keys::AfterSubcommand => &args[args.len().min(1)..args.len().min(2)],
+            Keys::Cursor => &args[..0],
+            Keys::Database => return None,
+        };
+
+        Some(keys.iter().map(Vec::as_slice).collect())
+    }
+
+    pub fn block(&self, args: &[Vec<u8>]) -> Option<Block> {
+        self.block.and_then(|block| block(args))
+    }
+
+    fn blocks(&mut self, block: BlockHandler) {
+        self.flags |= BLOCKING;
+        self.block = Some(block);
+    }
+
+    pub fn lock<'a>(&self, store: &'a Store, db: usize, args: &[Vec<u8>]) -> Keyspace<'a> {
+        if let Keys::Cursor = self.keys {
+            let cursor = std::str::from_utf8(&args[0])
+                .ok()
+                .and_then(|cursor| cursor.parse().ok());
+            return store.lock_cursor(db, cursor.unwrap_or_default());
+        }
+
+        match self.keys(args) {
+            Some(keys) => store.lock_keys(db, &keys),
+            None => store.lock(db),
+        }
+    }
+}
+
+pub struct CommandTable {
+    commands: Vec<Command>,
+    index: HashMap<&'static [u8], usize>,
+}
+
+static COMMANDS: LazyLock<CommandTable> = LazyLock::new(CommandTable::new);
+
+pub fn commands() -> &'static CommandTable {
+    &COMMANDS
+}
+
+impl CommandTable {
+    fn new() -> Self {
+        let mut table = CommandTable {
+            commands: Vec::new(),
+            index: HashMap::new(),
+        };
+
+        table.keyspace("PING", -1, NO_AUTH | PUBSUB, Keys::Empty, |_, _, args| {
+            ping(args)
+        });
+        table.connection("QUIT", -1, NO_AUTH | PUBSUB);
+        table.keyspace("ECHO", 2, 0, Keys::Empty, |_, _, args| echo(args));
+        table.keyspace("COMMAND", -1, 0, Keys::Empty, |_, _, args| {
+            server::command(args)
+        });
+        table.server("AUTH", -2, NO_AUTH, connection::auth);
+        table.server("HELLO", -1, NO_AUTH, connection::hello);
+        table.server("SELECT", 2, 0, connection::select);
+        table.server("CLIENT", -2, 0, connection::client);
+        table.keyspace("PUBLISH", 3, 0, Keys::Empty, |shared, _, args| {
+            publish(shared, args)
+        });
+        table.keyspace("PUBSUB", -2, 0, Keys::Empty, |shared, _, args| {
+            pubsub(shared, args)
+        });
+        table.connection("SUBSCRIBE", -2, PUBSUB);
+        table.connection("UNSUBSCRIBE", -1, PUBSUB);
+        table.connection("PSUBSCRIBE", -2, PUBSUB);
+        table.connection("PUNSUBSCRIBE", -1, PUBSUB);
+
+        table.keyspace("GET", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            strings::get(keyspace, args)
+        });
+        table.keyspace("GETDEL", 2, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            strings::getdel(keyspace, args)
+        });
+        table.keyspace(
+            "SET",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::set(keyspace, args),
+        );
+        table.keyspace("MGET", -2, 0, Keys::All, |_, keyspace, args| {
+            strings::mget(keyspace, args)
+        });
+        table.keyspace(
+            "MSET",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::Pairs,
+            |_, keyspace, args| strings::mset(keyspace, args),
+        );
+        table.keyspace(
+            "SETEX",
+            4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::setex(keyspace, args),
+        );
+        table.keyspace(
+            "APPEND",
+            3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::append(keyspace, args),
+        );
+        table.keyspace("STRLEN", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            strings::strlen(keyspace, args)
+        });
+        table.keyspace("GETRANGE", 4, 0, Keys::Leading(1), |_, keyspace, args| {
+            strings::getrange(keyspace, args)
+        });
+        table.keyspace(
+            "SETRANGE",
+            4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::setrange(keyspace, args),
+        );
+        table.keyspace(
+            "INCR",
+            2,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::incr_by(keyspace, args, 1, "incr"),
+        );
+        table.keyspace(
+            "DECR",
+            2,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::incr_by(keyspace, args, -1, "decr"),
+        );
+        table.keyspace(
+            "INCRBY",
+            3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::incrby(keyspace, args, 1, "incrby"),
+        );
+        table.keyspace(
+            "DECRBY",
+            3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::incrby(keyspace, args, -1, "decrby"),
+        );
+        table.keyspace(
+            "INCRBYFLOAT",
+            3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| strings::incrbyfloat(keyspace, args),
+        );
+
+        table.keyspace("DEL", -2, WRITE, Keys::All, |_, keyspace, args| {
+            keys::del(keyspace, args)
+        });
+        table.keyspace("EXISTS", -2, 0, Keys::All, |_, keyspace, args| {
+            keys::exists(keyspace, args)
+        });
+        table.keyspace(
+            "EXPIRE",
+            -3,
+            WRITE,
+            Keys::Leading(1),
+            |_, keyspace, args| keys::expire(keyspace, args),
+        );
+        table.keyspace(
+            "EXPIREAT",
+            -3,
+            WRITE,
+            Keys::Leading(1),
+            |_, keyspace, args| keys::expireat(keyspace, args, Duration::from_secs(1), "expireat"),
+        );
+        table.keyspace(
+            "PEXPIREAT",
+            -3,
+            WRITE,
+            Keys::Leading(1),
+            |_, keyspace, args| {
+                keys::expireat(keyspace, args, Duration::from_millis(1), "pexpireat")
+            },
+        );
+        table.keyspace("TTL", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            keys::ttl(keyspace, args, Duration::from_secs(1), "ttl")
+        });
+        table.keyspace("PTTL", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            keys::ttl(keyspace, args, Duration::from_millis(1), "pttl")
+        });
+        table.keyspace(
+            "PERSIST",
+            2,
+            WRITE,
+            Keys::Leading(1),
+            |_, keyspace, args| keys::persist(keyspace, args),
+        );
+        table.keyspace("KEYS", 2, 0, Keys::Database, |_, keyspace, args| {
+            keys::keys(keyspace, args)
+        });
+        table.keyspace("SCAN", -2, 0, Keys::Cursor, |_, keyspace, args| {
+            keys::scan(keyspace, args)
+        });
+        table.keyspace("TYPE", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            keys::type_of(keyspace, args)
+        });
+        table.keyspace(
+            "OBJECT",
+            -2,
+            0,
+            Keys::AfterSubcommand,
+            |_, keyspace, args| keys::object(keyspace, args),
+        );
+        table.keyspace("RENAME", 3, WRITE, Keys::Leading(2), |_, keyspace, args| {
+            keys::rename(keyspace, args)
+        });
+        table.keyspace(
+            "RENAMENX",
+            3,
+            WRITE,
+            Keys::Leading(2),
+            |_, keyspace, args| keys::renamenx(keyspace, args),
+        );
+        table.keyspace("DUMP", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            keys::dump(keyspace, args)
+        });
+        table.keyspace(
+            "RESTORE",
+            -4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| keys::restore(keyspace, args),
+        );
+        table.server("COPY", -3, WRITE | DENY_OOM, |shared, client, args| {
+            keys::copy(shared, client, args)
+        });
+        table.keyspace("DBSIZE", 1, 0, Keys::Database, |_, keyspace, _| {
+            keys::dbsize(keyspace)
+        });
+        table.keyspace("RANDOMKEY", 1, 0, Keys::Database, |shared, keyspace, _| {
+            keys::randomkey(shared, keyspace)
+        });
+        table.keyspace("FLUSHDB", 1, WRITE, Keys::Database, |_, keyspace, _| {
+            keys::flushdb(keyspace)
+        });
+        table.server("FLUSHALL", 1, WRITE, |shared, _, _| keys::flushall(shared));
+
+        table.server("INFO", -1, 0, |shared, _, args| server::info(shared, args));
+        table.server("SAVE", 1, 0, |shared, _, _| server::save(shared));
+        table.server("BGSAVE", -1, 0, |shared, _, args| {
+            server::bgsave(shared, args)
+        });
+        table.server("CONFIG", -2, 0, |shared, _, args| {
+            server::config(shared, args)
+        });
+        table.keyspace("WAIT", 3, 0, Keys::Empty, |_, _, args| server::wait(args));
+        table.keyspace("TIME", 1, 0, Keys::Empty, |_, _, _| server::time());
+        table
+            .server("DEBUG", -2, 0, |shared, _, args| {
+                server::debug(shared, args)
+            })
+            .blocks(|args| match args {
+                [subcommand, args @ ..] if subcommand.eq_ignore_ascii_case(b"SLEEP") => {
+                    server::sleep_duration(args).ok().map(Block::Sleep)
+                }
+                _ => None,
+            });
+
+        table.keyspace(
+            "LPUSH",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| lists::push(keyspace, args, lists::End::Left, "lpush"),
+        );
+        table.keyspace(
+            "RPUSH",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| lists::push(keyspace, args, lists::End::Right, "rpush"),
+        );
+        table.keyspace("LPOP", -2, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            lists::pop(keyspace, args, lists::End::Left, "lpop")
+        });
+        table.keyspace("RPOP", -2, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            lists::pop(keyspace, args, lists::End::Right, "rpop")
+        });
+        table
+            .keyspace("BLPOP", -3, WRITE, Keys::AllButLast, |_, keyspace, args| {
+                lists::blocking_pop(keyspace, args, lists::End::Left, "blpop")
+            })
+            .blocks(lists::block_timeout);
+        table
+            .keyspace("BRPOP", -3, WRITE, Keys::AllButLast, |_, keyspace, args| {
+                lists::blocking_pop(keyspace, args, lists::End::Right, "brpop")
+            })
+            .blocks(lists::block_timeout);
+        table.keyspace("LLEN", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            lists::llen(keyspace, args)
+        });
+        table.keyspace("LINDEX", 3, 0, Keys::Leading(1), |_, keyspace, args| {
+            lists::lindex(keyspace, args)
+        });
+        table.keyspace("LRANGE", 4, 0, Keys::Leading(1), |_, keyspace, args| {
+            lists::lrange(keyspace, args)
+        });
+        table.keyspace(
+            "LMOVE",
+            5,
+            WRITE | DENY_OOM,
+            Keys::Leading(2),
+            |_, keyspace, args| lists::lmove(keyspace, args),
+        );
+        table.keyspace("LREM", 4, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            lists::lrem(keyspace, args)
+        });
+        table.keyspace("LTRIM", 4, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            lists::ltrim(keyspace, args)
+        });
+
+        table.keyspace(
+            "HSET",
+            -4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| hashes::hset(keyspace, args),
+        );
+        table.keyspace("HGET", 3, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hget(keyspace, args)
+        });
+        table.keyspace("HGETALL", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hgetall(keyspace, args)
+        });
+        table.keyspace("HDEL", -3, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hdel(keyspace, args)
+        });
+        table.keyspace("HEXISTS", 3, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hexists(keyspace, args)
+        });
+        table.keyspace("HLEN", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hlen(keyspace, args)
+        });
+        table.keyspace("HKEYS", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hkeys(keyspace, args)
+        });
+        table.keyspace("HVALS", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            hashes::hvals(keyspace, args)
+        });
+        table.keyspace(
+            "HINCRBY",
+            4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| hashes::hincrby(keyspace, args),
+        );
+        table.keyspace(
+            "HINCRBYFLOAT",
+            4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| hashes::hincrbyfloat(keyspace, args),
+        );
+
+        table.keyspace(
+            "SADD",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| sets::sadd(keyspace, args),
+        );
+        table.keyspace("SREM", -3, WRITE, Keys::Leading(1), |_, keyspace, args| {
+            sets::srem(keyspace, args)
+        });
+        table.keyspace("SISMEMBER", 3, 0, Keys::Leading(1), |_, keyspace, args| {
+            sets::sismember(keyspace, args)
+        });
+        table.keyspace("SMEMBERS", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            sets::smembers(keyspace, args)
+        });
+        table.keyspace("SCARD", 2, 0, Keys::Leading(1), |_, keyspace, args| {
+            sets::scard(keyspace, args)
+        });
+        table.keyspace("SPOP", -2, WRITE, Keys::Leading(1), sets::spop);
+        table.keyspace("SRANDMEMBER", -2, 0, Keys::Leading(1), sets::srandmember);
+        table.keyspace("SINTER", -2, 0, Keys::All, |_, keyspace, args| {
+            sets::combine(keyspace, args, sets::Operation::Intersection)
+        });
+        table.keyspace("SUNION", -2, 0, Keys::All, |_, keyspace, args| {
+            sets::combine(keyspace, args, sets::Operation::Union)
+        });
+        table.keyspace("SDIFF", -2, 0, Keys::All, |_, keyspace, args| {
+            sets::combine(keyspace, args, sets::Operation::Difference)
+        });
+        table.keyspace(
+            "SINTERSTORE",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::All,
+            |_, keyspace, args| {
+                sets::combine_store(keyspace, args, sets::Operation::Intersection, "sinterstore")
+            },
+        );
+        table.keyspace(
+            "SUNIONSTORE",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::All,
+            |_, keyspace, args| {
+                sets::combine_store(keyspace, args, sets::Operation::Union, "sunionstore")
+            },
+        );
+        table.keyspace(
+            "SDIFFSTORE",
+            -3,
+            WRITE | DENY_OOM,
+            Keys::All,
+            |_, keyspace, args| {
+                sets::combine_store(keyspace, args, sets::Operation::Difference, "sdiffstore")
+            },
+        );
+
+        table.keyspace(
+            "ZADD",
+            -4,
+            WRITE | DENY_OOM,
+            Keys::Leading(1),
+            |_, keyspace, args| zsets::zadd(keyspace, args),
+        );
+        table.keyspace("ZSCORE", 3, 0, Keys::Leading(1), |_, keyspace, args| {
+            zsets::zscore(keyspace, args)
+        });
+        table.keyspace("ZRANGE", -4, 0, Keys::Leading(1), |_, keyspace, args| {
+            zsets::zrange(keyspace, args)
+        });
+
+        table.server("MULTI", 1, 0, |_, client, _| transactions::multi(client));
+        table.server("EXEC", 1, 0, |_, _, _| error("ERR EXEC without MULTI"));
+        table.server("DISCARD", 1, 0, |_, _, _| {
+            error("ERR DISCARD without MULTI")
+        });
+        table.server("WATCH", -2, 0, transactions::watch);
+        table.server("UNWATCH", 1, 0, |_, client, _| {
+            transactions::unwatch(client)
+        });
+        table.server("RESET", 1, NO_AUTH | PUBSUB, |shared, client, _| {
+            connection::reset(shared, client)
+        });
+
+        table
+    }
+
+    fn keyspace(
+        &mut self,
+        name: &'static str,
+        arity: i64,
+        flags: u8,
+        keys: Keys,
+        handler: KeyspaceHandler,
+    ) -> &mut Command {
+        self.register(Command {
+            name,
+            arity,
+            flags,
+            keys,
+            handler: Handler::Keyspace(handler),
+            block: None,
+        })
+    }
+
+    fn server(
+        &mut self,
+        name: &'static str,
+        arity: i64,
+        flags: u8,
+        handler: ServerHandler,
+    ) -> &mut Command {
+        self.register(Command {
+            name,
+            arity,
+            flags,
+            keys: Keys::Database,
+            handler: Handler::Server(handler),
+            block: None,
+        })
+    }
+
+    fn connection(&mut self, name: &'static str, arity: i64, flags: u8) -> &mut Command {
+        self.register(Command {
+            name,
+            arity,
+            flags,
+            keys: Keys::Empty,
+            handler: Handler::Connection,
+            block: None,
+        })
+    }
+
+    fn register(&mut self, command: Command) -> &mut Command {
+        self.index
+            .insert(command.name.as_bytes(), self.commands.len());
+        self.commands.push(command);
+        self.commands.last_mut().unwrap()
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<&Command> {
+        self.index.get(name).map(|&index| &self.commands[index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::commands::table::commands;
+
+    #[test]
+    fn test_command_names_are_unique() {
+        let mut names = HashSet::new();
+        for command in commands().iter() {
+            assert!(names.insert(command.name), "duplicate {}", command.name);
+            assert_eq!(command.name, command.name.to_ascii_uppercase());
+        }
+
+        assert_eq!(names.len(), commands().len());
+    }
+
+    #[test]
+    fn test_command_arities_are_valid() {
+        for command in commands().iter() {
+            assert_ne!(command.arity, 0, "{}", command.name);
+            assert!(
+                command.has_valid_arity(command.arity.unsigned_abs() as usize),
+                "{}",
+                command.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_name() {
+        let get = commands().get(b"GET").unwrap();
+
+        assert_eq!(get.arity, 2);
+        assert!(get.flag_names().is_empty());
+        assert!(commands().get(b"get").is_none());
+        assert_eq!(
+            commands().get(b"SET").unwrap().flag_names(),
+            vec!["write", "denyoom"]
+        );
+    }
+
+    #[test]
+    fn test_connection_commands_are_flagged() {
+        assert_eq!(
+            commands().get(b"SUBSCRIBE").unwrap().flag_names(),
+            vec!["pubsub"]
+        );
+        assert_eq!(
+            commands().get(b"QUIT").unwrap().flag_names(),
+            vec!["no-auth", "pubsub"]
+        );
+        assert_eq!(
+            commands().get(b"BLPOP").unwrap().flag_names(),
+            vec!["write", "blocking"]
+        );
+        assert!(
+            commands()
+                .get(b"DEBUG")
+                .unwrap()
+                .block(&[b"SLEEP".to_vec(), b"0".to_vec()])
+                .is_some()
+        );
+        assert!(
+            commands()
+                .get(b"DEBUG")
+                .unwrap()
+                .block(&[b"SET-ACTIVE-EXPIRE".to_vec(), b"0".to_vec()])
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{
+    commands::{
+        OUT_OF_MEMORY, command_arity_error, error, ok, propagate, reclaim_memory,
+        table::{self, Handler, commands},
+        unknown_command, wrong_context,
+    },
+    connection::{Client, Transaction, WatchedKey},
+    parser::RespMessage,
+    server::Shared,
+};
+
+pub fn multi(client: &mut Client) -> RespMessage {
+    client.transaction = Some(Transaction::default());
+    ok()
+}
+
+pub fn watch(shared: &Shared, client: &mut Client, args: &[Vec<u8>]) -> RespMessage {
+    let keys: Vec<&[u8]> = args.iter().map(Vec::as_slice).collect();
+    let mut keyspace = shared.store.lock_keys(client.db, &keys);
+    for key in args {
+        if client
+            .watched
+            .iter()
+            .any(|watched| watched.db == client.db && watched.key == *key)
+        {
+            continue;
+        }
+        client.watched.push(WatchedKey {
+            db: client.db,
+            key: key.clone(),
+            version: keyspace.version(key),
+        });
+    }
+    ok()
+}
+
+pub fn unwatch(client: &mut Client) -> RespMessage {
+    client.watched.clear();
+    ok()
+}
+
+pub fn queue(shared: &Shared, client: &mut Client, parts: Vec<Vec<u8>>) -> RespMessage {
+    let Some(transaction) = client.transaction.as_mut() else {
+        return error("ERR no transaction in progress");
+    };
+
+    match parts[0].as_slice() {
+        b"EXEC" => {
+            let transaction = client.transaction.take().unwrap();
+            exec(shared, client, transaction)
+        }
+        b"DISCARD" => {
+            client.transaction = None;
+            client.watched.clear();
+            ok()
+        }
+        b"MULTI" => error("ERR MULTI calls can not be nested"),
+        b"WATCH" => error("ERR WATCH inside MULTI is not allowed"),
+        name => match commands().get(name) {
+            None => {
+                transaction.aborted = true;
+                unknown_command(name, &parts[1..])
+            }
+            Some(command) if !command.has_valid_arity(parts.len()) => {
+                transaction.aborted = true;
+                command_arity_error(name)
+            }
+            Some(_) => {
+                transaction.commands.push(parts);
+                RespMessage::SimpleString("QUEUED".to_string())
+            }
+        },
+    }
+}
+
+fn exec(shared: &Shared, client: &mut Client, transaction: Transaction) -> RespMessage {
+    let watched = std::mem::take(&mut client.watched);
+    if transaction.aborted {
+        return error("EXECABORT Transaction discarded because of previous errors.");
+    }
+    let queued: Vec<_> = transaction
+        .commands
+        .iter()
+        .map(|parts| (commands().get(&parts[0]).unwrap(), parts))
+        .collect();
+    if queued
+        .iter()
+        .any(|(command, _)| command.has_flag(table::DENY_OOM))
+        && !reclaim_memory(shared)
+    {
+        return error(OUT_OF_MEMORY);
+    }
+
+    let _exclusive = shared.exec.write().unwrap();
+    let conflict = watched.iter().any(|watched| {
+        shared
+            .store
+            .lock_keys(watched.db, &[&watched.key])
+            .version(&watched.key)
+            != watched.version
+    });
+    if conflict {
+        return RespMessage::Null;
+    }
+
+    let mut keyspace = shared.store.lock(client.db);
+    let mut replies = Vec::with_capacity(queued.len());
+    for (command, parts) in queued {
+        let reply = match command.handler {
+            Handler::Server(execute) => {
+                drop(keyspace);
+                let reply = execute(shared, client, &parts[1..]);
+                keyspace = shared.store.lock(client.db);
+                reply
+            }
+            Handler::Connection => wrong_context(command),
+            Handler::Keyspace(execute) => {
+                let reply = execute(shared, &mut keyspace, &parts[1..]);
+                keyspace.log_expired();
+                reply
+            }
+        };
+        propagate(shared, client.db, command, parts, &reply);
+        replies.push(reply);
+    }
+
+    RespMessage::Array(replies)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, dispatch, run};
+    use crate::connection::Client;
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    #[test]
+    fn test_transaction_runs_queued_commands() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["MULTI"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["SET", "foo", "1"])),
+            RespMessage::SimpleString("QUEUED".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["INCR", "foo"])),
+            RespMessage::SimpleString("QUEUED".to_string())
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Array(vec![
+                RespMessage::SimpleString("OK".to_string()),
+                RespMessage::Integer(2),
+            ])
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"2".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_blocking_commands_do_not_block_inside_exec() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["DEBUG", "SLEEP", "10"]));
+        dispatch(&shared, &mut client, command(&["BLPOP", "list", "10"]));
+
+        let started = std::time::Instant::now();
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Array(vec![
+                RespMessage::SimpleString("OK".to_string()),
+                RespMessage::Null,
+            ])
+        );
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_watched_key_modified_aborts_exec() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        run(&shared, command(&["SET", "foo", "1"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["WATCH", "foo"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        run(&shared, command(&["INCR", "foo"]));
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "100"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Null
+        );
+        assert_eq!(
+            run(&shared, command(&["GET", "foo"])),
+            RespMessage::BulkString(b"2".to_vec())
+        );
+        assert!(client.watched.is_empty());
+    }
+
+    #[test]
+    fn test_watched_key_created_aborts_exec() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["WATCH", "foo"]));
+        run(&shared, command(&["SET", "foo", "1"]));
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["DEL", "foo"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_watched_key_unchanged_runs_exec() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+        run(&shared, command(&["SET", "foo", "1"]));
+
+        dispatch(&shared, &mut client, command(&["WATCH", "foo", "missing"]));
+        run(&shared, command(&["SET", "other", "1"]));
+        run(&shared, command(&["GET", "foo"]));
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["INCR", "foo"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Array(vec![RespMessage::Integer(2)])
+        );
+    }
+
+    #[test]
+    fn test_unwatch_clears_watched_keys() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["WATCH", "foo"]));
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["UNWATCH"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        run(&shared, command(&["SET", "foo", "1"]));
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["INCR", "foo"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Array(vec![RespMessage::Integer(2)])
+        );
+    }
+
+    #[test]
+    fn test_watch_inside_multi() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["WATCH", "foo"])),
+            RespMessage::Error("ERR WATCH inside MULTI is not allowed".to_string())
+        );
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_discard_drops_queued_commands() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "1"]));
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["DISCARD"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Error("ERR EXEC without MULTI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_queued_command_aborts_exec() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+        dispatch(&shared, &mut client, command(&["SET", "foo", "1"]));
+        assert!(matches!(
+            dispatch(&shared, &mut client, command(&["FOOBAR"])),
+            RespMessage::Error(_)
+        ));
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["GET"])),
+            RespMessage::Error("ERR wrong number of arguments for 'get' command".to_string())
+        );
+
+        assert_eq!(
+            dispatch(&shared, &mut client, command(&["EXEC"])),
+            RespMessage::Error(
+                "EXECABORT Transaction discarded because of previous errors.".to_string()
+            )
+        );
+        assert_eq!(run(&shared, command(&["GET", "foo"])), RespMessage::Null);
+    }
+
+    #[test]
+    fn test_transaction_state_is_per_client() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        dispatch(&shared, &mut client, command(&["MULTI"]));
+
+        assert_eq!(
+            run(&shared, command(&["SET", "foo", "1"])),
+            RespMessage::SimpleString("OK".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_inside_transaction() {
+        let shared = Shared::default();
+        let mut client = Client::default();
+
+        for cmd in [
+            &["MULTI"][..],
+            &["SELECT", "1"],
+            &["SET", "foo", "1"],
+            &["EXEC"],
+        ] {
+            dispatch(&shared, &mut client, command(cmd));
+        }
+
+        assert_eq!(client.db, 1);
+        assert!(shared.store.lock(1).exists(b"foo"));
+        assert!(!shared.store.lock(0).exists(b"foo"));
+    }
+
+    #[test]
+    fn test_exec_is_atomic_across_server_commands_and_databases() {
+        let shared = Shared::default();
+        run(&shared, command(&["SET", "counter", "0"]));
+
+        let writers: Vec<_> = [0, 1]
+            .into_iter()
+            .map(|db| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    let mut client = Client::default();
+                    let other = (1 - db).to_string();
+                    for _ in 0..200 {
+                        for cmd in [
+                            &["SELECT", &other][..],
+                            &["WATCH", "watched"],
+                            &["SELECT", "0"],
+                            &["MULTI"],
+                            &["INCR", "counter"],
+                            &["SELECT", "0"],
+                            &["INCR", "counter"],
+                            &["EXEC"],
+                        ] {
+                            dispatch(&shared, &mut client, command(cmd));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..200 {
+            let RespMessage::BulkString(counter) = run(&shared, command(&["GET", "counter"]))
+            else {
+                panic!("counter is missing");
+            };
+            let counter: i64 = String::from_utf8(counter).unwrap().parse().unwrap();
+            assert_eq!(counter % 2, 0);
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(
+            run(&shared, command(&["GET", "counter"])),
+            RespMessage::BulkString(b"800".to_vec())
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    commands::{
+        NOT_A_FLOAT, NOT_AN_INTEGER, SYNTAX_ERROR, WRONG_TYPE, error, normalize_range, parse_float,
+        parse_integer, wrong_arity,
+    },
+    parser::RespMessage,
+    sorted_set::SortedSet,
+    store::{Keyspace, Value},
+};
+
+pub fn zadd(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, pairs @ ..] = args else {
+        return wrong_arity("zadd");
+    };
+    if pairs.is_empty() || pairs.len() % 2 != 0 {
+        return error(SYNTAX_ERROR);
+    }
+
+    let mut members = Vec::with_capacity(pairs.len() / 2);
+    for pair in pairs.chunks(2) {
+        let Some(score) = parse_float(&pair[0]) else {
+            return error(NOT_A_FLOAT);
+        };
+        members.push((score, &pair[1]));
+    }
+
+    let Value::SortedSet(set) =
+        keyspace.get_or_insert_with(key, || Value::SortedSet(SortedSet::new()))
+    else {
+        return error(WRONG_TYPE);
+    };
+
+    let added = members
+        .into_iter()
+        .filter(|(score, member)| set.insert(member.to_vec(), *score))
+        .count();
+    RespMessage::Integer(added as i64)
+}
+
+pub fn zscore(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, member] = args else {
+        return wrong_arity("zscore");
+    };
+
+    match keyspace.get(key) {
+        Some(Value::SortedSet(set)) => match set.score(member) {
+            Some(score) => RespMessage::BulkString(format_score(score)),
+            None => RespMessage::Null,
+        },
+        Some(_) => error(WRONG_TYPE),
+        None => RespMessage::Null,
+    }
+}
+
+pub fn zrange(keyspace: &mut Keyspace, args: &[Vec<u8>]) -> RespMessage {
+    let [key, start, stop, options @ ..] = args else {
+        return wrong_arity("zrange");
+    };
+    let with_scores = match options {
+        [] => false,
+        [option] if option.eq_ignore_ascii_case(b"WITHSCORES") => true,
+        _ => return error(SYNTAX_ERROR),
+    };
+    let (Some(start), Some(stop)) = (parse_integer(start), parse_integer(stop)) else {
+        return error(NOT_AN_INTEGER);
+    };
+
+    let set = match keyspace.get(key) {
+        Some(Value::SortedSet(set)) => set,
+        Some(_) => return error(WRONG_TYPE),
+        None => return RespMessage::Array(vec![]),
+    };
+    let Some((start, stop)) = normalize_range(start, stop, set.len()) else {
+        return RespMessage::Array(vec![]);
+    };
+
+    let mut elements = Vec::new();
+    for (member, score) in set.iter().skip(start).take(stop - start + 1) {
+        elements.push(RespMessage::BulkString(member.clone()));
+        if with_scores {
+            elements.push(RespMessage::BulkString(format_score(score)));
+        }
+    }
+
+    RespMessage::Array(elements)
+}
+
+fn format_score(score: f64) -> Vec<u8> {
+    score.to_string().into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{command, run};
+    use crate::parser::RespMessage;
+    use crate::server::Shared;
+
+    fn bulk_strings(items: &[&str]) -> RespMessage {
+        RespMessage::Array(
+            items
+                .iter()
+                .map(|item| RespMessage::BulkString(item.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_zadd_orders_members() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(
+                &shared,
+                command(&["ZADD", "z", "2", "b", "1", "c", "2", "a"])
+            ),
+            RespMessage::Integer(3)
+        );
+        assert_eq!(
+            run(&shared, command(&["ZADD", "z", "0", "b"])),
+            RespMessage::Integer(0)
+        );
+        assert_eq!(
+            run(&shared, command(&["ZRANGE", "z", "0", "-1"])),
+            bulk_strings(&["b", "c", "a"])
+        );
+        assert_eq!(
+            run(&shared, command(&["ZRANGE", "z", "-2", "-1"])),
+            bulk_strings(&["c", "a"])
+        );
+        assert_eq!(
+            run(&shared, command(&["TYPE", "z"])),
+            RespMessage::SimpleString("zset".to_string())
+        );
+    }
+
+    #[test]
+    fn test_zadd_rejects_invalid_score() {
+        let shared = Shared::default();
+
+        assert_eq!(
+            run(&shared, command(&["ZADD", "z", "abc", "a"])),
+            RespMessage::Error("ERR value is not a valid float".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["ZADD", "z", "1", "a", "2"])),
+            RespMessage::Error("ERR syntax error".to_string())
+        );
+        assert_eq!(
+            run(&shared, command(&["EXISTS", "z"])),
+            RespMessage::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_zscore() {
+        let shared = Shared::default();
+        run(&shared, command(&["ZADD", "z", "1.5", "a", "-inf", "b"]));
+
+        assert_eq!(
+            run(&shared, command(&["ZSCORE", "z", "a"])),
+            RespMessage::BulkString(b"1.5".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["ZSCORE", "z", "b"])),
+            RespMessage::BulkString(b"-inf".to_vec())
+        );
+        assert_eq!(
+            run(&shared, command(&["ZSCORE", "z", "missing"])),
+            RespMessage::Null
+        );
+        assert_eq!(
+            run(&shared, command(&["ZSCORE", "missing", "a"])),
+            RespMessage::Null
+        );
+    }
+
+    #[test]
+    fn test_zrange_withscores() {
+        let shared = Shared::default();
+        run(&shared, command(&["ZADD", "z", "1", "a", "2.5", "b"]));
+
+        assert_eq!(
+            run(&shared, command(&["ZRANGE", "z", "0", "-1", "withscores"])),
+            bulk_strings(&["a", "1", "b", "2.5"])
+        );
+        assert_eq!(
+            run(&shared, command(&["ZRANGE", "z", "0", "-1", "REV"])),
+            RespMessage::Error("ERR syntax error".to_string())
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use std::{io, path::PathBuf, time::Duration};
+
+use tracing::Level;
+
+use crate::store::DEFAULT_DATABASES;
+
+pub const PARAMETERS: [&str; 16] = [
+    "bind",
+    "port",
+    "unixsocket",
+    "maxclients",
+    "databases",
+    "appendonly",
+    "appendfilename",
+    "appendfsync",
+    "dbfilename",
+    "requirepass",
+    "timeout",
+    "tls-cert-file",
+    "tls-key-file",
+    "loglevel",
+    "maxmemory",
+    "maxmemory-policy",
+];
+
+const MUTABLE_PARAMETERS: [&str; 6] = [
+    "maxclients",
+    "dbfilename",
+    "requirepass",
+    "timeout",
+    "maxmemory",
+    "maxmemory-policy",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub bind_addr: String,
+    pub port: u16,
+    pub unixsocket: Option<PathBuf>,
+    pub max_clients: usize,
+    pub databases: usize,
+    pub timeout: Duration,
+    pub requirepass: Option<String>,
+    pub dbfilename: PathBuf,
+    pub appendonly: bool,
+    pub appendfilename: PathBuf,
+    pub appendfsync: AppendFsync,
+    pub tls_cert_file: Option<PathBuf>,
+    pub tls_key_file: Option<PathBuf>,
+    pub log_level: Level,
+    pub maxmemory: usize,
+    pub maxmemory_policy: MaxmemoryPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AppendFsync {
+    Always,
+    #[default]
+    EverySec,
+    No,
+}
+
+impl std::str::FromStr for AppendFsync {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(AppendFsync::Always),
+            "everysec" => Ok(AppendFsync::EverySec),
+            "no" => Ok(AppendFsync::No),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for AppendFsync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AppendFsync::Always => "always",
+            AppendFsync::EverySec => "everysec",
+            AppendFsync::No => "no",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MaxmemoryPolicy {
+    #[default]
+    NoEviction,
+    AllKeysRandom,
+    VolatileTtl,
+}
+
+impl std::str::FromStr for MaxmemoryPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "noeviction" => Ok(MaxmemoryPolicy::NoEviction),
+            "allkeys-random" => Ok(MaxmemoryPolicy::AllKeysRandom),
+            "volatile-ttl" => Ok(MaxmemoryPolicy::VolatileTtl),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for MaxmemoryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MaxmemoryPolicy::NoEviction => "noeviction",
+            MaxmemoryPolicy::AllKeysRandom => "allkeys-random",
+            MaxmemoryPolicy::VolatileTtl => "volatile-ttl",
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind_addr: "127.0.0.1".to_string(),
+            port: 6379,
+            unixsocket: None,
+            max_clients: 10000,
+            databases: DEFAULT_DATABASES,
+            timeout: Duration::ZERO,
+            requirepass: None,
+            dbfilename: PathBuf::from("dump.rdb"),
+            appendonly: false,
+            appendfilename: PathBuf::from("appendonly.aof"),
+            appendfsync: AppendFsync::default(),
+            tls_cert_file: None,
+            tls_key_file: None,
+            log_level: Level::INFO,
+            maxmemory: 0,
+            maxmemory_policy: MaxmemoryPolicy::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> io::Result<Self> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Missing value for {}", flag),
+                )
+            })?;
+
+            let Some(name) = flag.strip_prefix("--") else {
+                return Err(unknown_argument(&flag));
+            };
+            config.apply(name, &value)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.bind_addr, self.port)
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "bind" => self.bind_addr.clone(),
+            "port" => self.port.to_string(),
+            "unixsocket" => display_path(&self.unixsocket),
+            "maxclients" => self.max_clients.to_string(),
+            "databases" => self.databases.to_string(),
+            "appendonly" => if self.appendonly { "yes" } else { "no" }.to_string(),
+            "appendfilename" => self.appendfilename.display().to_string(),
+            "appendfsync" => self.appendfsync.to_string(),
+            "dbfilename" => self.dbfilename.display().to_string(),
+            "requirepass" => self.requirepass.clone().unwrap_or_default(),
+            "timeout" => self.timeout.as_secs().to_string(),
+            "tls-cert-file" => display_path(&self.tls_cert_file),
+            "tls-key-file" => display_path(&self.tls_key_file),
+            "loglevel" => self.log_level.to_string().to_lowercase(),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.to_string(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> io::Result<()> {
+        if !MUTABLE_PARAMETERS.contains(&name) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "can't set immutable config",
+            ));
+        }
+
+        self.apply(name, value)
+    }
+
+    fn apply(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "bind" => self.bind_addr = value.to_string(),
+            "port" => self.port = parse_value(name, value)?,
+            "unixsocket" => self.unixsocket = Some(PathBuf::from(value)),
+            "maxclients" => self.max_clients = parse_value(name, value)?,
+            "databases" => match parse_value(name, value)? {
+                0 => return Err(invalid_value(name, value)),
+                databases => self.databases = databases,
+            },
+            "appendonly" => self.appendonly = parse_flag(name, value)?,
+            "appendfilename" => self.appendfilename = PathBuf::from(value),
+            "appendfsync" => self.appendfsync = parse_value(name, value)?,
+            "dbfilename" => self.dbfilename = PathBuf::from(value),
+            "requirepass" => {
+                self.requirepass = Some(value.to_string()).filter(|value| !value.is_empty())
+            }
+            "timeout" => self.timeout = Duration::from_secs(parse_value(name, value)?),
+            "tls-cert-file" => self.tls_cert_file = Some(PathBuf::from(value)),
+            "tls-key-file" => self.tls_key_file = Some(PathBuf::from(value)),
+            "loglevel" => self.log_level = parse_value(name, value)?,
+            "maxmemory" => self.maxmemory = parse_value(name, value)?,
+            "maxmemory-policy" => self.maxmemory_policy = parse_value(name, value)?,
+            _ => return Err(unknown_argument(name)),
+        }
+
+        Ok(())
+    }
+}
+
+fn display_path(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default()
+}
+
+fn unknown_argument(flag: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown argument {}", flag),
+    )
+}
+
+fn invalid_value(flag: &str, value: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid value for {}: {}", flag, value),
+    )
+}
+
+fn parse_flag(flag: &str, value: &str) -> io::Result<bool> {
+    match value {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(invalid_value(flag, value)),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_value(flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{AppendFsync, Config, MaxmemoryPolicy};
+
+    fn args(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|part| part.to_string()).collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::from_args(args(&[])).unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(config.addr(), "127.0.0.1:6379");
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let config = Config::from_args(args(&[
+            "--bind",
+            "0.0.0.0",
+            "--port",
+            "7000",
+            "--unixsocket",
+            "/tmp/redis-clone.sock",
+            "--maxclients",
+            "5",
+            "--databases",
+            "4",
+            "--timeout",
+            "30",
+            "--requirepass",
+            "secret",
+            "--dbfilename",
+            "backup.rdb",
+            "--appendonly",
+            "yes",
+            "--appendfsync",
+            "always",
+            "--tls-cert-file",
+            "server.crt",
+            "--tls-key-file",
+            "server.key",
+            "--loglevel",
+            "debug",
+            "--maxmemory",
+            "1048576",
+            "--maxmemory-policy",
+            "allkeys-random",
+        ]))
+        .unwrap();
+
+        assert_eq!(config.bind_addr, "0.0.0.0");
+        assert_eq!(config.port, 7000);
+        assert_eq!(
+            config.unixsocket,
+            Some(std::path::PathBuf::from("/tmp/redis-clone.sock"))
+        );
+        assert_eq!(config.max_clients, 5);
+        assert_eq!(config.databases, 4);
+        assert_eq!(config.timeout, std::time::Duration::from_secs(30));
+        assert_eq!(config.requirepass.as_deref(), Some("secret"));
+        assert_eq!(config.dbfilename, std::path::PathBuf::from("backup.rdb"));
+        assert!(config.appendonly);
+        assert_eq!(config.appendfsync, AppendFsync::Always);
+        assert_eq!(
+            config.tls_cert_file,
+            Some(std::path::PathBuf::from("server.crt"))
+        );
+        assert_eq!(
+            config.tls_key_file,
+            Some(std::path::PathBuf::from("server.key"))
+        );
+        assert_eq!(config.log_level, tracing::Level::DEBUG);
+        assert_eq!(config.get("loglevel").as_deref(), Some("debug"));
+        assert_eq!(config.maxmemory, 1048576);
+        assert_eq!(config.maxmemory_policy, MaxmemoryPolicy::AllKeysRandom);
+    }
+
+    #[test]
+    fn test_invalid_port() {
+        let result = Config::from_args(args(&["--port", "not-a-port"]));
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_invalid_databases() {
+        for value in ["0", "-1"] {
+            let result = Config::from_args(args(&["--databases", value]));
+
+            assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_invalid_appendfsync() {
+        assert!(Config::from_args(args(&["--appendfsync", "sometimes"])).is_err());
+        assert!(Config::from_args(args(&["--appendonly", "maybe"])).is_err());
+    }
+
+    #[test]
+    fn test_missing_value() {
+        assert!(Config::from_args(args(&["--port"])).is_err());
+    }
+
+    #[test]
+    fn test_unknown_argument() {
+        assert!(Config::from_args(args(&["--nope", "1"])).is_err());
+    }
+
+    #[test]
+    fn test_set_parameter() {
+        let mut config = Config::default();
+
+        config.set("maxclients", "100").unwrap();
+        config.set("requirepass", "secret").unwrap();
+
+        assert_eq!(config.get("maxclients").as_deref(), Some("100"));
+        assert_eq!(config.requirepass.as_deref(), Some("secret"));
+        assert!(config.set("maxclients", "many").is_err());
+        assert!(config.set("port", "7000").is_err());
+        assert_eq!(config.get("nope"), None);
+    }
+}
@@ -0,0 +1,455 @@
+use std::{collections::HashMap, sync::atomic::Ordering, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter, ErrorKind},
+    sync::{broadcast, mpsc, watch},
+    task::JoinHandle,
+};
+
+use tracing::debug;
+
+use crate::{
+    commands::{
+        dispatch_command,
+        table::{self, Block, Handler, commands},
+        wrong_arity,
+    },
+    config::Config,
+    parser::{RespMessage, RespParser, RespVersion},
+    pubsub::PubSub,
+    server::Shared,
+};
+
+const REQUEST_QUEUE_LEN: usize = 16;
+
+#[derive(Default)]
+pub struct Client {
+    pub id: u64,
+    pub addr: String,
+    pub name: Option<Vec<u8>>,
+    pub db: usize,
+    pub authenticated: bool,
+    pub protocol: RespVersion,
+    pub transaction: Option<Transaction>,
+    pub watched: Vec<WatchedKey>,
+}
+
+impl Client {
+    pub fn new(id: u64) -> Self {
+        Client {
+            id,
+            ..Client::default()
+        }
+    }
+
+    pub fn is_authenticated(&self, config: &Config) -> bool {
+        self.authenticated || config.requirepass.is_none()
+    }
+}
+
+#[derive(Default)]
+pub struct Transaction {
+    pub commands: Vec<Vec<Vec<u8>>>,
+    pub aborted: bool,
+}
+
+pub struct WatchedKey {
+    pub db: usize,
+    pub key: Vec<u8>,
+    pub version: Option<u64>,
+}
+
+struct Delivery {
+    pattern: Option<Vec<u8>>,
+    channel: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+impl Delivery {
+    fn into_message(self) -> RespMessage {
+        let mut fields = Vec::with_capacity(4);
+        match self.pattern {
+            Some(pattern) => {
+                fields.push(RespMessage::BulkString(b"pmessage".to_vec()));
+                fields.push(RespMessage::BulkString(pattern));
+            }
+            None => fields.push(RespMessage::BulkString(b"message".to_vec())),
+        }
+        fields.push(RespMessage::BulkString(self.channel));
+        fields.push(RespMessage::BulkString(self.payload));
+        RespMessage::Push(fields)
+    }
+}
+
+struct Subscriptions {
+    channels: HashMap<Vec<u8>, JoinHandle<()>>,
+    patterns: HashMap<Vec<u8>, JoinHandle<()>>,
+    sender: mpsc::UnboundedSender<Delivery>,
+    receiver: mpsc::UnboundedReceiver<Delivery>,
+}
+
+impl Subscriptions {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Subscriptions {
+            channels: HashMap::new(),
+            patterns: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    fn subscribe(&mut self, pubsub: &PubSub, channel: &[u8]) {
+        if self.channels.contains_key(channel) {
+            return;
+        }
+
+        let name = channel.to_vec();
+        let task = forward(
+            pubsub.subscribe(channel),
+            self.sender.clone(),
+            move |payload| Delivery {
+                pattern: None,
+                channel: name.clone(),
+                payload,
+            },
+        );
+        self.channels.insert(channel.to_vec(), task);
+    }
+
+    fn psubscribe(&mut self, pubsub: &PubSub, pattern: &[u8]) {
+        if self.patterns.contains_key(pattern) {
+            return;
+        }
+
+        let name = pattern.to_vec();
+        let task = forward(
+            pubsub.psubscribe(pattern),
+            self.sender.clone(),
+            move |(channel, payload)| Delivery {
+                pattern: Some(name.clone()),
+                channel,
+                payload,
+            },
+        );
+        self.patterns.insert(pattern.to_vec(), task);
+    }
+
+    fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    fn execute(
+        &mut self,
+        pubsub: &PubSub,
+        protocol: RespVersion,
+        name: &[u8],
+        args: Vec<Vec<u8>>,
+    ) -> Vec<u8> {
+        let name = name.to_ascii_lowercase();
+        let mut reply = Vec::new();
+        match name.as_slice() {
+            b"subscribe" | b"psubscribe" => {
+                for target in args {
+                    if name == b"subscribe" {
+                        self.subscribe(pubsub, &target);
+                    } else {
+                        self.psubscribe(pubsub, &target);
+                    }
+                    confirmation(&name, Some(target), self.count())
+                        .encode_for(protocol, &mut reply);
+                }
+            }
+            _ => {
+                let pattern = name == b"punsubscribe";
+                let targets = if args.is_empty() {
+                    let subscribed = if pattern {
+                        &self.patterns
+                    } else {
+                        &self.channels
+                    };
+                    let mut targets: Vec<Vec<u8>> = subscribed.keys().cloned().collect();
+                    targets.sort_unstable();
+                    targets
+                } else {
+                    args
+                };
+                if targets.is_empty() {
+                    confirmation(&name, None, self.count()).encode_for(protocol, &mut reply);
+                }
+                for target in targets {
+                    let subscribed = if pattern {
+                        &mut self.patterns
+                    } else {
+                        &mut self.channels
+                    };
+                    if let Some(task) = subscribed.remove(&target) {
+                        task.abort();
+                    }
+                    confirmation(&name, Some(target), self.count())
+                        .encode_for(protocol, &mut reply);
+                }
+            }
+        }
+        reply
+    }
+}
+
+fn forward<T: Clone + Send + 'static>(
+    mut receiver: broadcast::Receiver<T>,
+    sender: mpsc::UnboundedSender<Delivery>,
+    deliver: impl Fn(T) -> Delivery + Send + 'static,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => {
+                    if sender.send(deliver(message)).is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    })
+}
+
+fn confirmation(kind: &[u8], target: Option<Vec<u8>>, count: usize) -> RespMessage {
+    RespMessage::Push(vec![
+        RespMessage::BulkString(kind.to_vec()),
+        target.map_or(RespMessage::Null, RespMessage::BulkString),
+        RespMessage::Integer(count as i64),
+    ])
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for task in self.channels.values().chain(self.patterns.values()) {
+            task.abort();
+        }
+    }
+}
+
+enum Event {
+    Request(tokio::io::Result<Option<RespMessage>>),
+    Published(Delivery),
+    Shutdown,
+}
+
+struct Requests {
+    receiver: mpsc::Receiver<tokio::io::Result<Option<RespMessage>>>,
+    task: JoinHandle<()>,
+}
+
+impl Requests {
+    fn spawn<R: AsyncRead + Unpin + Send + 'static>(mut parser: RespParser<R>) -> Self {
+        let (sender, receiver) = mpsc::channel(REQUEST_QUEUE_LEN);
+        let task = tokio::spawn(async move {
+            loop {
+                let request = parser.next_message().await;
+                let last = !matches!(request, Ok(Some(_)));
+                if sender.send(request).await.is_err() || last {
+                    return;
+                }
+            }
+        });
+
+        Requests { receiver, task }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    async fn next(&mut self, idle_timeout: Duration) -> tokio::io::Result<Option<RespMessage>> {
+        let request = async { self.receiver.recv().await.unwrap_or(Ok(None)) };
+        if idle_timeout.is_zero() {
+            return request.await;
+        }
+
+        tokio::time::timeout(idle_timeout, request)
+            .await
+            .unwrap_or(Ok(None))
+    }
+}
+
+impl Drop for Requests {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn pop_blocking(
+    shared: &Shared,
+    client: &mut Client,
+    shutdown: &mut watch::Receiver<bool>,
+    name: Vec<u8>,
+    args: Vec<Vec<u8>>,
+    timeout: Duration,
+) -> RespMessage {
+    let waiter = shared.blocking.wait_for(client.db, &args[..args.len() - 1]);
+    let deadline = (!timeout.is_zero()).then(|| tokio::time::Instant::now() + timeout);
+
+    loop {
+        let reply = dispatch_command(shared, client, name.clone(), args.clone());
+        if reply != RespMessage::Null {
+            return reply;
+        }
+
+        let expired = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = waiter.woken() => {}
+            _ = expired => return RespMessage::Null,
+            _ = shutdown.wait_for(|stopping| *stopping) => return RespMessage::Null,
+        }
+    }
+}
+
+pub async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
+    socket: S,
+    shared: Shared,
+    mut client: Client,
+    mut shutdown: watch::Receiver<bool>,
+) -> tokio::io::Result<()> {
+    let (reader, writer) = tokio::io::split(socket);
+    let mut writer = BufWriter::new(writer);
+    let mut requests = Requests::spawn(RespParser::new(reader).with_inline_commands(true));
+    let mut subscriptions = Subscriptions::new();
+
+    loop {
+        if requests.is_empty() {
+            writer.flush().await?;
+        }
+
+        let idle_timeout = shared.config().timeout;
+        let event = tokio::select! {
+            message = requests.next(idle_timeout) => Event::Request(message),
+            Some(delivery) = subscriptions.receiver.recv() => Event::Published(delivery),
+            _ = shutdown.wait_for(|stopping| *stopping) => Event::Shutdown,
+        };
+
+        let message = match event {
+            Event::Request(Ok(Some(message))) => message,
+            Event::Request(Ok(None)) | Event::Shutdown => return writer.flush().await,
+            Event::Request(Err(e)) if e.kind() == ErrorKind::InvalidData => {
+                debug!(error = %e, "closing connection after protocol error");
+                let reply = RespMessage::Error(format!("ERR Protocol error: {}", e));
+                writer.write_all(&reply.encode()).await?;
+                return writer.flush().await;
+            }
+            Event::Request(Err(e))
+                if matches!(
+                    e.kind(),
+                    ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset
+                ) =>
+            {
+                let _ = writer.flush().await;
+                return Ok(());
+            }
+            Event::Request(Err(e)) => return Err(e),
+            Event::Published(delivery) => {
+                let mut buf = Vec::new();
+                delivery
+                    .into_message()
+                    .encode_for(client.protocol, &mut buf);
+                writer.write_all(&buf).await?;
+                continue;
+            }
+        };
+
+        shared
+            .stats
+            .commands_processed
+            .fetch_add(1, Ordering::Relaxed);
+        let (name, args) = match message.into_command() {
+            Ok(command) => command,
+            Err(e) => {
+                debug!(error = %e, "malformed command");
+                let reply = RespMessage::Error(format!("ERR Protocol error: {}", e));
+                writer.write_all(&reply.encode()).await?;
+                continue;
+            }
+        };
+
+        let command = commands()
+            .get(&name.to_ascii_uppercase())
+            .filter(|command| {
+                command.has_flag(table::NO_AUTH) || client.is_authenticated(&shared.config())
+            });
+        let subscribed = subscriptions.count() > 0;
+        if let Some(command) =
+            command.filter(|command| matches!(command.handler, Handler::Connection))
+        {
+            if !command.has_valid_arity(args.len() + 1) {
+                let reply = wrong_arity(&command.name.to_lowercase());
+                writer.write_all(&reply.encode()).await?;
+            } else if command.name == "QUIT" {
+                writer
+                    .write_all(&RespMessage::SimpleString("OK".to_string()).encode())
+                    .await?;
+                return writer.flush().await;
+            } else {
+                let reply = subscriptions.execute(
+                    &shared.pubsub,
+                    client.protocol,
+                    command.name.as_bytes(),
+                    args,
+                );
+                writer.write_all(&reply).await?;
+            }
+        } else if subscribed
+            && client.protocol == RespVersion::Resp2
+            && command.is_some_and(|command| command.name == "PING")
+            && args.len() <= 1
+        {
+            let reply = RespMessage::Array(vec![
+                RespMessage::BulkString(b"pong".to_vec()),
+                RespMessage::BulkString(args.into_iter().next().unwrap_or_default()),
+            ]);
+            writer.write_all(&reply.encode()).await?;
+        } else if subscribed
+            && client.protocol == RespVersion::Resp2
+            && !command.is_some_and(|command| command.has_flag(table::PUBSUB))
+        {
+            let reply = RespMessage::Error(format!(
+                "ERR Can't execute '{}': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+                String::from_utf8_lossy(&name).to_lowercase()
+            ));
+            writer.write_all(&reply.encode()).await?;
+        } else if let Some(block) = command
+            .filter(|_| client.transaction.is_none())
+            .and_then(|command| command.block(&args))
+        {
+            let reply = match block {
+                Block::Sleep(duration) => {
+                    tokio::time::sleep(duration).await;
+                    RespMessage::SimpleString("OK".to_string())
+                }
+                Block::Pop(timeout) => {
+                    writer.flush().await?;
+                    pop_blocking(&shared, &mut client, &mut shutdown, name, args, timeout).await
+                }
+            };
+            let mut buf = Vec::new();
+            reply.encode_for(client.protocol, &mut buf);
+            writer.write_all(&buf).await?;
+        } else {
+            let command = String::from_utf8_lossy(&name).to_lowercase();
+            let reply = dispatch_command(&shared, &mut client, name, args);
+            if let RespMessage::Error(message) = &reply {
+                debug!(%command, %message, "command failed");
+            } else if command == "reset" {
+                subscriptions = Subscriptions::new();
+            }
+            let mut buf = Vec::new();
+            reply.encode_for(client.protocol, &mut buf);
+            writer.write_all(&buf).await?;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+pub fn glob_match(pattern: &[u8], key: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => key.is_empty(),
+        Some((b'*', rest)) => {
+            let rest = trim_leading_stars(rest);
+            if rest.is_empty() {
+                return true;
+            }
+            (0..=key.len()).any(|start| glob_match(rest, &key[start..]))
+        }
+        Some((b'?', rest)) => !key.is_empty() && glob_match(rest, &key[1..]),
+        Some((b'[', rest)) => {
+            let Some((&first, key_rest)) = key.split_first() else {
+                return false;
+            };
+            let (matched, rest) = match_class(rest, first);
+            matched && glob_match(rest, key_rest)
+        }
+        Some((b'\\', rest)) if !rest.is_empty() => {
+            key.first() == Some(&rest[0]) && glob_match(&rest[1..], &key[1..])
+        }
+        Some((&literal, rest)) => key.first() == Some(&literal) && glob_match(rest, &key[1..]),
+    }
+}
+
+fn trim_leading_stars(mut pattern: &[u8]) -> &[u8] {
+    while let Some((b'*', rest)) = pattern.split_first() {
+        pattern = rest;
+    }
+    pattern
+}
+
+fn match_class(mut pattern: &[u8], byte: u8) -> (bool, &[u8]) {
+    let negate = pattern.first() == Some(&b'^');
+    if negate {
+        pattern = &pattern[1..];
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern {
+            [] => break,
+            [b']', rest @ ..] => {
+                pattern = rest;
+                break;
+            }
+            [b'\\', escaped, rest @ ..] => {
+                matched |= *escaped == byte;
+                pattern = rest;
+            }
+            [start, b'-', end, rest @ ..] if *end != b']' => {
+                let (low, high) = if start <= end {
+                    (*start, *end)
+                } else {
+                    (*end, *start)
+                };
+                matched |= (low..=high).contains(&byte);
+                pattern = rest;
+            }
+            [literal, rest @ ..] => {
+                matched |= *literal == byte;
+                pattern = rest;
+            }
+        }
+    }
+
+    (matched != negate, pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::glob::glob_match;
+
+    #[test]
+    fn test_star() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"*", b"anything"));
+        assert!(glob_match(b"h*llo", b"hllo"));
+        assert!(glob_match(b"h*llo", b"heeeello"));
+        assert!(!glob_match(b"h*llo", b"hello world"));
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert!(glob_match(b"user:*", b"user:1"));
+        assert!(glob_match(b"user:*", b"user:"));
+        assert!(!glob_match(b"user:*", b"session:1"));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(glob_match(b"h?llo", b"hallo"));
+        assert!(!glob_match(b"h?llo", b"hllo"));
+        assert!(!glob_match(b"h?llo", b"heello"));
+    }
+
+    #[test]
+    fn test_character_class() {
+        assert!(glob_match(b"h[ae]llo", b"hello"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[ae]llo", b"hillo"));
+        assert!(glob_match(b"h[^e]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-c]llo", b"hbllo"));
+        assert!(!glob_match(b"h[a-c]llo", b"hdllo"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(glob_match(b"h\\*llo", b"h*llo"));
+        assert!(!glob_match(b"h\\*llo", b"hello"));
+        assert!(glob_match(b"[\\]]", b"]"));
+    }
+}
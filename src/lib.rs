@@ -1,2 +1,14 @@
+pub mod aof;
+pub mod blocking;
+pub mod clients;
+pub mod commands;
+pub mod config;
+pub mod connection;
+pub mod glob;
 pub mod parser;
+pub mod pubsub;
+pub mod rdb;
 pub mod server;
+pub mod sorted_set;
+pub mod store;
+pub mod tls;
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use redis_clone::server::Server;
+use redis_clone::{config::Config, server::Server};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
@@ -13,10 +13,13 @@ async fn main() -> tokio::io::Result<()> {
     let ready_for_server = ready.clone();
     let ready_for_client = ready;
 
-    let addr = "127.0.0.1:8080".to_string();
-    let stream_addr = addr.clone();
+    let config = Config::from_args(std::env::args().skip(1))?;
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level)
+        .init();
+    let stream_addr = config.addr();
 
-    let server = Server::new(addr);
+    let server = Server::new(config);
 
     tokio::spawn(async move {
         server.listen_with_signal(ready_for_server).await.unwrap();
@@ -25,7 +28,7 @@ async fn main() -> tokio::io::Result<()> {
     ready_for_client.notified().await;
 
     let mut stream = TcpStream::connect(stream_addr).await?;
-    stream.write_all(b"Hello from client").await?;
+    stream.write_all(b"*1\r\n$4\r\nPING\r\n").await?;
 
     let mut response = [0; 1024];
     let n = stream.read(&mut response).await?;
@@ -1,9 +1,17 @@
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
+use std::str::FromStr;
 
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt};
 
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum RespVersion {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum RespMessage {
     SimpleString(String),
@@ -12,103 +20,540 @@ pub enum RespMessage {
     BulkString(Vec<u8>),
     Array(Vec<RespMessage>),
     Null,
+    Boolean(bool),
+    Double(f64),
+    Map(Vec<(RespMessage, RespMessage)>),
+    Set(Vec<RespMessage>),
+    Push(Vec<RespMessage>),
+}
+
+impl RespMessage {
+    pub fn into_command(self) -> io::Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        let RespMessage::Array(items) = self else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Command is not an array",
+            ));
+        };
+
+        let mut parts = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                RespMessage::BulkString(bytes) => parts.push(bytes),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Command arguments must be bulk strings",
+                    ));
+                }
+            }
+        }
+        if parts.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Empty command"));
+        }
+
+        let name = parts.remove(0);
+        Ok((name, parts))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode_into(&mut buf);
+        buf
+    }
+
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        self.encode_for(RespVersion::Resp2, buf);
+    }
+
+    pub fn encode_for(&self, proto: RespVersion, buf: &mut Vec<u8>) {
+        match self {
+            RespMessage::SimpleString(s) => {
+                buf.push(b'+');
+                buf.extend_from_slice(s.as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespMessage::Error(e) => {
+                buf.push(b'-');
+                buf.extend_from_slice(e.as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespMessage::Integer(n) => {
+                buf.push(b':');
+                buf.extend_from_slice(n.to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespMessage::BulkString(data) => {
+                buf.push(b'$');
+                buf.extend_from_slice(data.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                buf.extend_from_slice(data);
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespMessage::Array(items) => {
+                buf.push(b'*');
+                buf.extend_from_slice(items.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for item in items {
+                    item.encode_for(proto, buf);
+                }
+            }
+            RespMessage::Null => match proto {
+                RespVersion::Resp2 => buf.extend_from_slice(b"$-1\r\n"),
+                RespVersion::Resp3 => buf.extend_from_slice(b"_\r\n"),
+            },
+            RespMessage::Boolean(b) if proto == RespVersion::Resp2 => {
+                RespMessage::Integer(*b as i64).encode_for(proto, buf);
+            }
+            RespMessage::Boolean(b) => {
+                buf.extend_from_slice(if *b { b"#t\r\n" } else { b"#f\r\n" });
+            }
+            RespMessage::Set(items) => {
+                buf.push(match proto {
+                    RespVersion::Resp2 => b'*',
+                    RespVersion::Resp3 => b'~',
+                });
+                buf.extend_from_slice(items.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for item in items {
+                    item.encode_for(proto, buf);
+                }
+            }
+            RespMessage::Push(items) => {
+                buf.push(match proto {
+                    RespVersion::Resp2 => b'*',
+                    RespVersion::Resp3 => b'>',
+                });
+                buf.extend_from_slice(items.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for item in items {
+                    item.encode_for(proto, buf);
+                }
+            }
+            RespMessage::Map(pairs) => {
+                let len = match proto {
+                    RespVersion::Resp2 => {
+                        buf.push(b'*');
+                        pairs.len() * 2
+                    }
+                    RespVersion::Resp3 => {
+                        buf.push(b'%');
+                        pairs.len()
+                    }
+                };
+                buf.extend_from_slice(len.to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for (key, value) in pairs {
+                    key.encode_for(proto, buf);
+                    value.encode_for(proto, buf);
+                }
+            }
+            RespMessage::Double(d) if proto == RespVersion::Resp2 => {
+                RespMessage::BulkString(format_double(*d).into_bytes()).encode_for(proto, buf);
+            }
+            RespMessage::Double(d) => {
+                buf.push(b',');
+                buf.extend_from_slice(format_double(*d).as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+        }
+    }
 }
 
+fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        "nan".to_string()
+    } else if d == f64::INFINITY {
+        "inf".to_string()
+    } else if d == f64::NEG_INFINITY {
+        "-inf".to_string()
+    } else {
+        d.to_string()
+    }
+}
+
+pub const DEFAULT_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+pub const DEFAULT_MAX_FRAME_LEN: usize = 512 * 1024 * 1024;
+pub const DEFAULT_MAX_FRAME_ELEMENTS: usize = 1024 * 1024;
+pub const DEFAULT_MAX_LINE_LEN: usize = 64 * 1024;
+const MAX_PREALLOCATED_ELEMENTS: usize = 1024;
+
 pub struct RespParser<R: AsyncRead + Unpin> {
     reader: tokio::io::BufReader<R>,
+    max_bulk_len: usize,
+    max_depth: usize,
+    depth: usize,
+    max_frame_len: usize,
+    max_frame_elements: usize,
+    max_line_len: usize,
+    frame_len: usize,
+    frame_elements: usize,
+    inline_commands: bool,
 }
 
 impl<R: AsyncRead + Unpin + Send> RespParser<R> {
     pub fn new(inner: R) -> Self {
-        return RespParser {
+        RespParser {
             reader: tokio::io::BufReader::new(inner),
-        };
+            max_bulk_len: DEFAULT_MAX_BULK_LEN,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            max_frame_elements: DEFAULT_MAX_FRAME_ELEMENTS,
+            max_line_len: DEFAULT_MAX_LINE_LEN,
+            frame_len: 0,
+            frame_elements: 0,
+            inline_commands: false,
+        }
+    }
+
+    pub fn with_max_bulk_len(mut self, max_bulk_len: usize) -> Self {
+        self.max_bulk_len = max_bulk_len;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    pub fn with_max_frame_elements(mut self, max_frame_elements: usize) -> Self {
+        self.max_frame_elements = max_frame_elements;
+        self
+    }
+
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    pub fn with_inline_commands(mut self, inline_commands: bool) -> Self {
+        self.inline_commands = inline_commands;
+        self
+    }
+
+    pub async fn next_message(&mut self) -> io::Result<Option<RespMessage>> {
+        if self.reader.fill_buf().await?.is_empty() {
+            return Ok(None);
+        }
+
+        self.parse_next().await.map(Some)
     }
 
     pub fn parse_next<'a>(
         &'a mut self,
     ) -> Pin<Box<dyn Future<Output = tokio::io::Result<RespMessage>> + Send + 'a>> {
         Box::pin(async move {
+            if self.depth == 0 {
+                self.frame_len = 0;
+                self.frame_elements = 0;
+            }
+            self.frame_elements += 1;
+            if self.frame_elements > self.max_frame_elements {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Request element count exceeds limit",
+                ));
+            }
+
             let mut prefix = [0u8];
             self.reader.read_exact(&mut prefix).await?;
 
-            match prefix[0] {
+            let aggregate = matches!(prefix[0], b'*' | b'%' | b'~' | b'>');
+            if aggregate {
+                if self.depth >= self.max_depth {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Nesting depth exceeds limit",
+                    ));
+                }
+                self.depth += 1;
+            }
+
+            let result = match prefix[0] {
                 b'+' => self.parse_simple_string().await,
                 b':' => self.parse_integer().await,
                 b'$' => self.parse_bulk_string().await,
                 b'*' => self.parse_array().await,
+                b'_' => self.parse_null().await,
+                b'#' => self.parse_boolean().await,
+                b',' => self.parse_double().await,
+                b'%' => self.parse_map().await,
+                b'~' => self.parse_set().await,
+                b'>' => self.parse_push().await,
+                first if self.inline_commands => self.parse_inline(first).await,
                 _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown prefix")),
+            };
+
+            if aggregate {
+                self.depth -= 1;
             }
+
+            result
         })
     }
 
     pub async fn parse_simple_string(&mut self) -> io::Result<RespMessage> {
         let content = self.read_line().await?;
-        Ok(RespMessage::SimpleString(content.to_string()))
+        let content = String::from_utf8(content)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid simple string"))?;
+
+        Ok(RespMessage::SimpleString(content))
     }
 
     pub async fn parse_integer(&mut self) -> io::Result<RespMessage> {
         let line = self.read_line().await?;
-        let n = line
-            .parse()
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid integer format"))?;
+        let n = parse_number(&line, "Invalid integer format")?;
 
         Ok(RespMessage::Integer(n))
     }
 
     pub async fn parse_bulk_string(&mut self) -> io::Result<RespMessage> {
         let line = self.read_line().await?;
-        let length: i64 = line.parse().map_err(|_| {
-            io::Error::new(io::ErrorKind::InvalidData, "Invalid bulk string length")
-        })?;
+        let length: i64 = parse_number(&line, "Invalid bulk string length")?;
 
         if length == -1 {
             return Ok(RespMessage::Null);
         }
 
+        if length < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid bulk string length",
+            ));
+        }
+
+        if length > self.max_bulk_len as i64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Bulk string length exceeds limit",
+            ));
+        }
+
+        self.frame_len += length as usize;
+        if self.frame_len > self.max_frame_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Request size exceeds limit",
+            ));
+        }
+
         let mut buffer = vec![0u8; length as usize];
         self.reader.read_exact(&mut buffer).await?;
 
         let mut crlf = [0u8; 2];
         self.reader.read_exact(&mut crlf).await?;
 
+        if &crlf != b"\r\n" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Bulk string is not terminated by CRLF",
+            ));
+        }
+
         Ok(RespMessage::BulkString(buffer))
     }
 
     pub async fn parse_array(&mut self) -> io::Result<RespMessage> {
         let line = self.read_line().await?;
-        let length: i64 = line
-            .parse()
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid array length"))?;
+        let length: i64 = parse_number(&line, "Invalid array length")?;
+
+        if length == -1 {
+            return Ok(RespMessage::Null);
+        }
+
+        Ok(RespMessage::Array(
+            self.parse_elements(length, "Invalid array length").await?,
+        ))
+    }
+
+    pub async fn parse_set(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        let length: i64 = parse_number(&line, "Invalid set length")?;
+
+        Ok(RespMessage::Set(
+            self.parse_elements(length, "Invalid set length").await?,
+        ))
+    }
+
+    pub async fn parse_push(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        let length: i64 = parse_number(&line, "Invalid push length")?;
+
+        Ok(RespMessage::Push(
+            self.parse_elements(length, "Invalid push length").await?,
+        ))
+    }
+
+    async fn parse_elements(
+        &mut self,
+        length: i64,
+        error: &'static str,
+    ) -> io::Result<Vec<RespMessage>> {
+        if length < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+        }
+
+        let mut elements = Vec::with_capacity((length as usize).min(MAX_PREALLOCATED_ELEMENTS));
+        for _ in 0..length {
+            elements.push(self.parse_next().await?);
+        }
+
+        Ok(elements)
+    }
+
+    pub async fn parse_map(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        let length: i64 = parse_number(&line, "Invalid map length")?;
 
-        let mut array = Vec::with_capacity(length as usize);
+        if length < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid map length",
+            ));
+        }
+
+        let mut pairs = Vec::with_capacity((length as usize).min(MAX_PREALLOCATED_ELEMENTS));
         for _ in 0..length {
-            array.push(self.parse_next().await?);
+            let key = self.parse_next().await?;
+            let value = self.parse_next().await?;
+            pairs.push((key, value));
         }
 
-        Ok(RespMessage::Array(array))
+        Ok(RespMessage::Map(pairs))
+    }
+
+    pub async fn parse_null(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        if !line.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid null format",
+            ));
+        }
+
+        Ok(RespMessage::Null)
+    }
+
+    pub async fn parse_boolean(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        match line.as_slice() {
+            b"t" => Ok(RespMessage::Boolean(true)),
+            b"f" => Ok(RespMessage::Boolean(false)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid boolean format",
+            )),
+        }
+    }
+
+    pub async fn parse_double(&mut self) -> io::Result<RespMessage> {
+        let line = self.read_line().await?;
+        let d = match line.as_slice() {
+            b"inf" => f64::INFINITY,
+            b"-inf" => f64::NEG_INFINITY,
+            b"nan" => f64::NAN,
+            _ => parse_number(&line, "Invalid double format")?,
+        };
+
+        Ok(RespMessage::Double(d))
+    }
+
+    pub async fn parse_inline(&mut self, first: u8) -> io::Result<RespMessage> {
+        let mut line = vec![first];
+        line.extend(self.read_line().await?);
+
+        let args = line
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| RespMessage::BulkString(arg.to_vec()))
+            .collect();
+
+        Ok(RespMessage::Array(args))
     }
 
-    async fn read_line(&mut self) -> io::Result<String> {
-        let mut line = String::new();
-        self.reader.read_line(&mut line).await?;
+    async fn read_line(&mut self) -> io::Result<Vec<u8>> {
+        let mut line = Vec::new();
+        let limit = self.max_line_len as u64 + 2;
+        (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut line)
+            .await?;
 
-        if line.is_empty() {
+        if !line.ends_with(b"\n") {
+            if line.len() as u64 == limit {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Line length exceeds limit",
+                ));
+            }
             return Err(io::Error::new(
                 io::ErrorKind::UnexpectedEof,
                 "Connection closed",
             ));
         }
 
-        Ok(line.trim_end_matches("\r\n").to_string())
+        if line.ends_with(b"\r\n") {
+            line.truncate(line.len() - 2);
+        }
+
+        Ok(line)
     }
 }
 
+fn parse_number<T: FromStr>(line: &[u8], message: &'static str) -> io::Result<T> {
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
-    use crate::parser::{RespMessage, RespParser};
+    use crate::parser::{RespMessage, RespParser, RespVersion};
+
+    #[test]
+    fn test_into_command() {
+        let message = RespMessage::Array(vec![
+            RespMessage::BulkString(b"SET".to_vec()),
+            RespMessage::BulkString(b"foo".to_vec()),
+            RespMessage::BulkString(b"bar".to_vec()),
+        ]);
+
+        assert_eq!(
+            message.into_command().unwrap(),
+            (b"SET".to_vec(), vec![b"foo".to_vec(), b"bar".to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_into_command_empty_array() {
+        let result = RespMessage::Array(vec![]).into_command();
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_into_command_non_bulk_element() {
+        let message = RespMessage::Array(vec![
+            RespMessage::BulkString(b"GET".to_vec()),
+            RespMessage::Integer(1),
+        ]);
+
+        assert_eq!(
+            message.into_command().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+        assert!(RespMessage::Integer(1).into_command().is_err());
+    }
 
     #[tokio::test]
     async fn test_parse_simple_string() {
@@ -152,4 +597,486 @@ mod tests {
             ])
         );
     }
+
+    #[tokio::test]
+    async fn test_parse_resp3_null() {
+        let data = b"_\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Null);
+    }
+
+    #[tokio::test]
+    async fn test_parse_resp3_null_with_payload() {
+        let data = b"_x\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_boolean_true() {
+        let data = b"#t\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Boolean(true));
+    }
+
+    #[tokio::test]
+    async fn test_parse_boolean_false() {
+        let data = b"#f\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Boolean(false));
+    }
+
+    #[tokio::test]
+    async fn test_parse_boolean_invalid() {
+        let data = b"#true\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_double() {
+        let data = b",1.5\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Double(1.5));
+    }
+
+    #[tokio::test]
+    async fn test_parse_double_infinity() {
+        let data = b",-inf\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Double(f64::NEG_INFINITY));
+    }
+
+    #[tokio::test]
+    async fn test_parse_double_invalid() {
+        let data = b",pi\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_map() {
+        let data = b"%2\r\n$3\r\none\r\n:1\r\n$3\r\ntwo\r\n:2\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Map(vec![
+                (
+                    RespMessage::BulkString(b"one".to_vec()),
+                    RespMessage::Integer(1)
+                ),
+                (
+                    RespMessage::BulkString(b"two".to_vec()),
+                    RespMessage::Integer(2)
+                ),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_map_negative_length() {
+        let data = b"%-1\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_set() {
+        let data = b"~3\r\n$1\r\na\r\n$1\r\nb\r\n:3\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Set(vec![
+                RespMessage::BulkString(b"a".to_vec()),
+                RespMessage::BulkString(b"b".to_vec()),
+                RespMessage::Integer(3),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_push() {
+        let data = b">2\r\n$7\r\nmessage\r\n:1\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Push(vec![
+                RespMessage::BulkString(b"message".to_vec()),
+                RespMessage::Integer(1),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_bulk_string_too_large() {
+        let data = b"$1000000000000\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_bulk_string_custom_limit() {
+        let data = b"$5\r\nhello\r\n";
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_bulk_len(4);
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_bulk_string_negative_length() {
+        let data = b"$-5\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_nested_array_within_depth() {
+        let data = [b"*1\r\n".repeat(3), b":1\r\n".to_vec()].concat();
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_depth(3);
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Array(vec![RespMessage::Array(vec![RespMessage::Array(vec![
+                RespMessage::Integer(1)
+            ])])])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_nested_array_too_deep() {
+        let data = [b"*1\r\n".repeat(200), b":1\r\n".to_vec()].concat();
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_null_array() {
+        let data = b"*-1\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::Null);
+    }
+
+    #[tokio::test]
+    async fn test_parse_array_negative_length() {
+        let data = b"*-2\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_array_short_stream() {
+        let data = b"*3\r\n:1\r\n:2\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_array_huge_claimed_length() {
+        let data = b"*4294967296\r\n:1\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_array_exceeds_frame_element_limit() {
+        let data = [b"*100\r\n".to_vec(), b":1\r\n".repeat(100)].concat();
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_frame_elements(50);
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_array_exceeds_frame_len_limit() {
+        let data = [b"*4\r\n".to_vec(), b"$4\r\nabcd\r\n".repeat(4)].concat();
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_frame_len(10);
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_line_exceeds_limit() {
+        let data = [b"*1\r\n$".to_vec(), vec![b'1'; 64], b"\r\n".to_vec()].concat();
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_line_len(16);
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_inline_exceeds_line_limit() {
+        let data = [vec![b'a'; 64], b"\r\n".to_vec()].concat();
+        let mut parser = RespParser::new(Cursor::new(data))
+            .with_inline_commands(true)
+            .with_max_line_len(16);
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_line_at_limit() {
+        let data = b"+abcd\r\n";
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_line_len(4);
+
+        assert_eq!(
+            parser.parse_next().await.unwrap(),
+            RespMessage::SimpleString("abcd".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frame_budget_resets_between_messages() {
+        let data = b"*2\r\n:1\r\n:2\r\n*2\r\n:3\r\n:4\r\n";
+        let mut parser = RespParser::new(Cursor::new(data)).with_max_frame_elements(3);
+
+        assert!(parser.parse_next().await.is_ok());
+        assert!(parser.parse_next().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_parse_simple_string_invalid_utf8() {
+        let data = b"+caf\xe9\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_simple_string_utf8() {
+        let data = "+café\r\n".as_bytes();
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(result, RespMessage::SimpleString("café".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_parse_bulk_string_bad_terminator() {
+        let data = b"$5\r\nhelloXX";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_parse_inline_ping() {
+        let data = b"PING\r\n";
+        let mut parser = RespParser::new(Cursor::new(data)).with_inline_commands(true);
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Array(vec![RespMessage::BulkString(b"PING".to_vec())])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_inline_set() {
+        let data = b"SET foo  bar\r\n";
+        let mut parser = RespParser::new(Cursor::new(data)).with_inline_commands(true);
+        let result = parser.parse_next().await.unwrap();
+
+        assert_eq!(
+            result,
+            RespMessage::Array(vec![
+                RespMessage::BulkString(b"SET".to_vec()),
+                RespMessage::BulkString(b"foo".to_vec()),
+                RespMessage::BulkString(b"bar".to_vec()),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_inline_rejected_in_strict_mode() {
+        let data = b"PING\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.parse_next().await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_next_message_until_clean_eof() {
+        let data = b"+OK\r\n:1\r\n";
+        let mut parser = RespParser::new(Cursor::new(data));
+
+        assert_eq!(
+            parser.next_message().await.unwrap(),
+            Some(RespMessage::SimpleString("OK".to_string()))
+        );
+        assert_eq!(
+            parser.next_message().await.unwrap(),
+            Some(RespMessage::Integer(1))
+        );
+        assert_eq!(parser.next_message().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_next_message_eof_mid_frame() {
+        let data = b"*2\r\n$3\r\nfoo\r\n$3\r\nba";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.next_message().await;
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_message_eof_mid_line() {
+        let data = b"+OK";
+        let mut parser = RespParser::new(Cursor::new(data));
+        let result = parser.next_message().await;
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    async fn assert_round_trip(data: &[u8]) {
+        let mut parser = RespParser::new(Cursor::new(data));
+        let message = parser.parse_next().await.unwrap();
+
+        assert_eq!(message.encode(), data);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_simple_string() {
+        assert_round_trip(b"+OK\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_integer() {
+        assert_round_trip(b":-42\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_bulk_string() {
+        assert_round_trip(b"$5\r\nhello\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_null() {
+        assert_round_trip(b"$-1\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_nested_array() {
+        assert_round_trip(b"*2\r\n*2\r\n:1\r\n$3\r\nfoo\r\n+OK\r\n").await;
+    }
+
+    #[test]
+    fn test_encode_error() {
+        let message = RespMessage::Error("ERR boom".to_string());
+
+        assert_eq!(message.encode(), b"-ERR boom\r\n");
+    }
+
+    #[test]
+    fn test_encode_map_per_protocol() {
+        let message = RespMessage::Map(vec![(
+            RespMessage::BulkString(b"a".to_vec()),
+            RespMessage::Integer(1),
+        )]);
+
+        let mut resp2 = Vec::new();
+        message.encode_for(RespVersion::Resp2, &mut resp2);
+        let mut resp3 = Vec::new();
+        message.encode_for(RespVersion::Resp3, &mut resp3);
+
+        assert_eq!(resp2, b"*2\r\n$1\r\na\r\n:1\r\n");
+        assert_eq!(resp3, b"%1\r\n$1\r\na\r\n:1\r\n");
+    }
+
+    #[test]
+    fn test_encode_set_per_protocol() {
+        let message = RespMessage::Set(vec![RespMessage::BulkString(b"a".to_vec())]);
+
+        let mut resp2 = Vec::new();
+        message.encode_for(RespVersion::Resp2, &mut resp2);
+        let mut resp3 = Vec::new();
+        message.encode_for(RespVersion::Resp3, &mut resp3);
+
+        assert_eq!(resp2, b"*1\r\n$1\r\na\r\n");
+        assert_eq!(resp3, b"~1\r\n$1\r\na\r\n");
+    }
+
+    #[test]
+    fn test_encode_push_per_protocol() {
+        let message = RespMessage::Push(vec![RespMessage::BulkString(b"a".to_vec())]);
+
+        let mut resp2 = Vec::new();
+        message.encode_for(RespVersion::Resp2, &mut resp2);
+        let mut resp3 = Vec::new();
+        message.encode_for(RespVersion::Resp3, &mut resp3);
+
+        assert_eq!(resp2, b"*1\r\n$1\r\na\r\n");
+        assert_eq!(resp3, b">1\r\n$1\r\na\r\n");
+    }
+
+    #[test]
+    fn test_encode_scalars_per_protocol() {
+        for (message, resp2, resp3) in [
+            (RespMessage::Null, &b"$-1\r\n"[..], &b"_\r\n"[..]),
+            (RespMessage::Boolean(true), b":1\r\n", b"#t\r\n"),
+            (RespMessage::Double(1.5), b"$3\r\n1.5\r\n", b",1.5\r\n"),
+        ] {
+            let mut buf = Vec::new();
+            message.encode_for(RespVersion::Resp2, &mut buf);
+            assert_eq!(buf, resp2, "{:?}", message);
+
+            buf.clear();
+            message.encode_for(RespVersion::Resp3, &mut buf);
+            assert_eq!(buf, resp3, "{:?}", message);
+        }
+    }
 }
@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+
+use crate::glob::glob_match;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+type Channels = HashMap<Vec<u8>, broadcast::Sender<Vec<u8>>>;
+type Patterns = HashMap<Vec<u8>, broadcast::Sender<(Vec<u8>, Vec<u8>)>>;
+
+#[derive(Clone, Default)]
+pub struct PubSub {
+    channels: Arc<Mutex<Channels>>,
+    patterns: Arc<Mutex<Patterns>>,
+}
+
+impl PubSub {
+    pub fn new() -> Self {
+        PubSub::default()
+    }
+
+    pub fn subscribe(&self, channel: &[u8]) -> broadcast::Receiver<Vec<u8>> {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get(channel) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                channels.insert(channel.to_vec(), sender);
+                receiver
+            }
+        }
+    }
+
+    pub fn psubscribe(&self, pattern: &[u8]) -> broadcast::Receiver<(Vec<u8>, Vec<u8>)> {
+        let mut patterns = self.patterns.lock().unwrap();
+        match patterns.get(pattern) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                patterns.insert(pattern.to_vec(), sender);
+                receiver
+            }
+        }
+    }
+
+    pub fn channels(&self) -> Vec<Vec<u8>> {
+        let mut channels: Vec<Vec<u8>> = self
+            .channels
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, sender)| sender.receiver_count() > 0)
+            .map(|(channel, _)| channel.clone())
+            .collect();
+        channels.sort_unstable();
+        channels
+    }
+
+    pub fn subscribers(&self, channel: &[u8]) -> usize {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(channel)
+            .map_or(0, |sender| sender.receiver_count())
+    }
+
+    pub fn publish(&self, channel: &[u8], message: Vec<u8>) -> usize {
+        let mut receivers = 0;
+
+        let mut patterns = self.patterns.lock().unwrap();
+        patterns.retain(|pattern, sender| {
+            if !glob_match(pattern, channel) {
+                return sender.receiver_count() > 0;
+            }
+            match sender.send((channel.to_vec(), message.clone())) {
+                Ok(count) => {
+                    receivers += count;
+                    true
+                }
+                Err(_) => false,
+            }
+        });
+        drop(patterns);
+
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(channel) {
+            match sender.send(message) {
+                Ok(count) => receivers += count,
+                Err(_) => {
+                    channels.remove(channel);
+                }
+            }
+        }
+
+        receivers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pubsub::PubSub;
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let pubsub = PubSub::new();
+
+        assert_eq!(pubsub.publish(b"news", b"hello".to_vec()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_subscribers() {
+        let pubsub = PubSub::new();
+        let mut first = pubsub.subscribe(b"news");
+        let mut second = pubsub.subscribe(b"news");
+
+        assert_eq!(pubsub.publish(b"news", b"hello".to_vec()), 2);
+        assert_eq!(first.recv().await.unwrap(), b"hello");
+        assert_eq!(second.recv().await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_pattern_subscribers() {
+        let pubsub = PubSub::new();
+        let mut exact = pubsub.subscribe(b"news.tech");
+        let mut pattern = pubsub.psubscribe(b"news.*");
+        let _unrelated = pubsub.psubscribe(b"sports.*");
+
+        assert_eq!(pubsub.publish(b"news.tech", b"hello".to_vec()), 2);
+        assert_eq!(exact.recv().await.unwrap(), b"hello");
+        assert_eq!(
+            pattern.recv().await.unwrap(),
+            (b"news.tech".to_vec(), b"hello".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_channels_with_subscribers() {
+        let pubsub = PubSub::new();
+        let _news = pubsub.subscribe(b"news");
+        let _also_news = pubsub.subscribe(b"news");
+        drop(pubsub.subscribe(b"sports"));
+
+        assert_eq!(pubsub.channels(), vec![b"news".to_vec()]);
+        assert_eq!(pubsub.subscribers(b"news"), 2);
+        assert_eq!(pubsub.subscribers(b"sports"), 0);
+    }
+
+    #[test]
+    fn test_dropped_subscriber_is_not_counted() {
+        let pubsub = PubSub::new();
+        let receiver = pubsub.subscribe(b"news");
+        drop(receiver);
+
+        assert_eq!(pubsub.publish(b"news", b"hello".to_vec()), 0);
+    }
+}
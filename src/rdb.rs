@@ -0,0 +1,373 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    sorted_set::SortedSet,
+    store::{Members, Store, Value},
+};
+
+const MAGIC: &[u8] = b"REDISCLONE";
+const VERSION: u32 = 1;
+
+const OPCODE_SELECT_DB: u8 = 0xFE;
+const OPCODE_EOF: u8 = 0xFF;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_HASH: u8 = 2;
+const TYPE_SET: u8 = 3;
+const TYPE_SORTED_SET: u8 = 4;
+
+const DUMP_TRAILER_LEN: usize = 4 + 8;
+
+pub fn save(store: &Store, path: &Path) -> io::Result<()> {
+    write_snapshot(&snapshot(store), path)
+}
+
+pub fn snapshot(store: &Store) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_be_bytes());
+
+    let now = SystemTime::now();
+    for db in 0..store.databases() {
+        let keyspace = store.lock(db);
+        if keyspace.is_empty() {
+            continue;
+        }
+
+        out.push(OPCODE_SELECT_DB);
+        write_u32(&mut out, db);
+        for (key, value, ttl) in keyspace.iter() {
+            match ttl {
+                Some(ttl) => {
+                    let deadline = (now + ttl).duration_since(UNIX_EPOCH).unwrap_or_default();
+                    out.push(1);
+                    out.extend_from_slice(&(deadline.as_millis() as u64).to_be_bytes());
+                }
+                None => out.push(0),
+            }
+            write_value(&mut out, key, value);
+        }
+    }
+    out.push(OPCODE_EOF);
+
+    out
+}
+
+pub fn write_snapshot(snapshot: &[u8], path: &Path) -> io::Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, snapshot)?;
+    fs::rename(temp, path)
+}
+
+pub fn load(store: &Store, path: &Path) -> io::Result<()> {
+    let data = fs::read(path)?;
+    let mut reader = Reader {
+        data: &data,
+        pos: 0,
+    };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("Invalid snapshot header"));
+    }
+    let version = u32::from_be_bytes(reader.take(4)?.try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid("Unsupported snapshot version"));
+    }
+
+    let now = SystemTime::now();
+    let mut db = 0;
+    loop {
+        match reader.byte()? {
+            OPCODE_EOF => return Ok(()),
+            OPCODE_SELECT_DB => {
+                db = reader.u32()?;
+                if db >= store.databases() {
+                    return Err(invalid("Snapshot database index is out of range"));
+                }
+            }
+            has_expiry @ (0 | 1) => {
+                let deadline = match has_expiry {
+                    1 => {
+                        let millis = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+                        Some(UNIX_EPOCH + Duration::from_millis(millis))
+                    }
+                    _ => None,
+                };
+                let (key, value) = reader.value()?;
+
+                let ttl = match deadline {
+                    Some(deadline) => match deadline.duration_since(now) {
+                        Ok(ttl) if !ttl.is_zero() => Some(ttl),
+                        _ => continue,
+                    },
+                    None => None,
+                };
+                let mut keyspace = store.lock(db);
+                keyspace.set(key.clone(), value);
+                if let Some(ttl) = ttl {
+                    keyspace.expire(&key, ttl);
+                }
+            }
+            _ => return Err(invalid("Invalid snapshot opcode")),
+        }
+    }
+}
+
+pub fn dump(value: &Value) -> Vec<u8> {
+    let mut out = vec![value_type(value)];
+    write_body(&mut out, value);
+    out.extend_from_slice(&VERSION.to_be_bytes());
+    let checksum = checksum(&out);
+    out.extend_from_slice(&checksum.to_be_bytes());
+    out
+}
+
+pub fn restore(payload: &[u8]) -> io::Result<Value> {
+    let Some(body_len) = payload.len().checked_sub(DUMP_TRAILER_LEN) else {
+        return Err(invalid("Truncated payload"));
+    };
+    let (signed, expected) = payload.split_at(payload.len() - 8);
+    if checksum(signed) != u64::from_be_bytes(expected.try_into().unwrap()) {
+        return Err(invalid("Payload checksum mismatch"));
+    }
+    if signed[body_len..] != VERSION.to_be_bytes() {
+        return Err(invalid("Unsupported payload version"));
+    }
+
+    let mut reader = Reader {
+        data: &payload[..body_len],
+        pos: 0,
+    };
+    let kind = reader.byte()?;
+    let value = reader.body(kind)?;
+    if reader.pos != body_len {
+        return Err(invalid("Trailing bytes in payload"));
+    }
+
+    Ok(value)
+}
+
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn value_type(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => TYPE_STRING,
+        Value::List(_) => TYPE_LIST,
+        Value::Hash(_) => TYPE_HASH,
+        Value::Set(_) => TYPE_SET,
+        Value::SortedSet(_) => TYPE_SORTED_SET,
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, key: &[u8], value: &Value) {
+    out.push(value_type(value));
+    write_bytes(out, key);
+    write_body(out, value);
+}
+
+fn write_body(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(bytes) => write_bytes(out, bytes),
+        Value::List(items) => {
+            write_u32(out, items.len());
+            items.iter().for_each(|item| write_bytes(out, item));
+        }
+        Value::Hash(fields) => {
+            write_u32(out, fields.len());
+            for (field, value) in fields {
+                write_bytes(out, field);
+                write_bytes(out, value);
+            }
+        }
+        Value::Set(members) => {
+            write_u32(out, members.len());
+            members.iter().for_each(|member| write_bytes(out, member));
+        }
+        Value::SortedSet(set) => {
+            write_u32(out, set.len());
+            for (member, score) in set.iter() {
+                write_bytes(out, member);
+                out.extend_from_slice(&score.to_bits().to_be_bytes());
+            }
+        }
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u32).to_be_bytes());
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> io::Result<&[u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("Truncated snapshot"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<usize> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.u32()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn value(&mut self) -> io::Result<(Vec<u8>, Value)> {
+        let kind = self.byte()?;
+        let key = self.bytes()?;
+        Ok((key, self.body(kind)?))
+    }
+
+    fn body(&mut self, kind: u8) -> io::Result<Value> {
+        let value = match kind {
+            TYPE_STRING => Value::String(self.bytes()?),
+            TYPE_LIST => {
+                let len = self.u32()?;
+                let mut items = VecDeque::new();
+                for _ in 0..len {
+                    items.push_back(self.bytes()?);
+                }
+                Value::List(items)
+            }
+            TYPE_HASH => {
+                let len = self.u32()?;
+                let mut fields = HashMap::new();
+                for _ in 0..len {
+                    fields.insert(self.bytes()?, self.bytes()?);
+                }
+                Value::Hash(fields)
+            }
+            TYPE_SET => {
+                let len = self.u32()?;
+                let mut members = Members::default();
+                for _ in 0..len {
+                    members.insert(self.bytes()?);
+                }
+                Value::Set(members)
+            }
+            TYPE_SORTED_SET => {
+                let len = self.u32()?;
+                let mut set = SortedSet::new();
+                for _ in 0..len {
+                    let member = self.bytes()?;
+                    let score = u64::from_be_bytes(self.take(8)?.try_into().unwrap());
+                    set.insert(member, f64::from_bits(score));
+                }
+                Value::SortedSet(set)
+            }
+            _ => return Err(invalid("Invalid snapshot value type")),
+        };
+
+        Ok(value)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, VecDeque},
+        path::PathBuf,
+        time::Duration,
+    };
+
+    use crate::rdb::{load, save};
+    use crate::sorted_set::SortedSet;
+    use crate::store::{Members, Store, Value};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("redis-clone-{}-{}.rdb", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let store = Store::default();
+        {
+            let mut keyspace = store.lock(0);
+            keyspace.set(b"string".to_vec(), Value::String(b"value".to_vec()));
+            keyspace.set(
+                b"list".to_vec(),
+                Value::List(VecDeque::from([b"a".to_vec(), b"b".to_vec()])),
+            );
+            keyspace.set(
+                b"hash".to_vec(),
+                Value::Hash(HashMap::from([(b"f".to_vec(), b"v".to_vec())])),
+            );
+            keyspace.set(b"ttl".to_vec(), Value::String(b"soon".to_vec()));
+            keyspace.expire(b"ttl", Duration::from_secs(100));
+        }
+        store.lock(3).set(
+            b"set".to_vec(),
+            Value::Set(Members::from_iter([b"m".to_vec()])),
+        );
+        let mut sorted = SortedSet::new();
+        sorted.insert(b"m".to_vec(), 1.5);
+        store
+            .lock(3)
+            .set(b"zset".to_vec(), Value::SortedSet(sorted));
+
+        let path = temp_path("round-trip");
+        save(&store, &path).unwrap();
+        let restored = Store::default();
+        load(&restored, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for db in 0..store.databases() {
+            let mut original = store.lock(db);
+            let mut loaded = restored.lock(db);
+            assert_eq!(original.len(), loaded.len());
+            let keys: Vec<Vec<u8>> = original.keys().into_iter().cloned().collect();
+            for key in keys {
+                assert_eq!(original.get(&key), loaded.get(&key));
+                assert_eq!(
+                    original.ttl(&key).unwrap().is_some(),
+                    loaded.ttl(&key).unwrap().is_some()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_bad_header() {
+        let path = temp_path("bad-header");
+        std::fs::write(&path, b"NOTASNAPSHOT").unwrap();
+
+        let result = load(&Store::default(), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -1,36 +1,910 @@
-use std::sync::Arc;
+use std::{
+    io,
+    path::Path,
+    sync::{
+        Arc, Mutex, RwLock, RwLockReadGuard,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
+use rand::{SeedableRng, rngs::StdRng};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
     net::TcpListener,
-    sync::Notify,
+    sync::{Notify, watch},
+    task::JoinSet,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::{Instrument, info, info_span, warn};
+
+use crate::{
+    aof::{self, Aof},
+    blocking::Blocking,
+    clients::Clients,
+    config::Config,
+    connection::{Client, handle_connection},
+    parser::RespMessage,
+    pubsub::PubSub,
+    rdb,
+    store::Store,
+    tls,
 };
 
+const EXPIRY_CYCLE_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+pub struct Shared {
+    pub store: Store,
+    pub pubsub: PubSub,
+    pub blocking: Blocking,
+    pub clients: Clients,
+    pub stats: Arc<Stats>,
+    pub config: Arc<RwLock<Config>>,
+    pub aof: Arc<Aof>,
+    pub rng: Arc<Mutex<StdRng>>,
+    pub exec: Arc<RwLock<()>>,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        let aof = Arc::<Aof>::default();
+        let blocking = Blocking::default();
+        Shared {
+            store: Store::default()
+                .with_aof(aof.clone())
+                .with_blocking(blocking.clone()),
+            pubsub: PubSub::default(),
+            blocking,
+            clients: Clients::default(),
+            stats: Arc::default(),
+            config: Arc::default(),
+            aof,
+            rng: Arc::new(Mutex::new(StdRng::from_os_rng())),
+            exec: Arc::default(),
+        }
+    }
+}
+
+impl Shared {
+    pub fn config(&self) -> RwLockReadGuard<'_, Config> {
+        self.config.read().unwrap()
+    }
+}
+
+pub struct Stats {
+    pub started_at: Instant,
+    pub commands_processed: AtomicU64,
+    pub connected_clients: AtomicUsize,
+    pub next_client_id: AtomicU64,
+    pub evicted_keys: AtomicU64,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            started_at: Instant::now(),
+            commands_processed: AtomicU64::new(0),
+            connected_clients: AtomicUsize::new(0),
+            next_client_id: AtomicU64::new(1),
+            evicted_keys: AtomicU64::new(0),
+        }
+    }
+}
+
 pub struct Server {
-    addr: String,
+    shared: Shared,
 }
 
 impl Server {
-    pub fn new(addr: String) -> Self {
-        return Server { addr };
+    pub fn new(config: Config) -> Self {
+        let aof = Arc::new(Aof::new(config.appendfsync));
+        let blocking = Blocking::default();
+        let shared = Shared {
+            store: Store::new(config.databases)
+                .with_aof(aof.clone())
+                .with_blocking(blocking.clone()),
+            aof,
+            blocking,
+            config: Arc::new(RwLock::new(config)),
+            ..Shared::default()
+        };
+
+        Server { shared }
     }
 
     pub async fn listen_with_signal(&self, signal: Arc<Notify>) -> tokio::io::Result<()> {
-        let listener = TcpListener::bind(&self.addr).await?;
+        self.listen_with_shutdown(signal, Arc::new(Notify::new()))
+            .await
+    }
+
+    pub async fn listen_with_shutdown(
+        &self,
+        ready: Arc<Notify>,
+        shutdown: Arc<Notify>,
+    ) -> tokio::io::Result<()> {
+        let config = self.shared.config().clone();
+        if config.appendonly {
+            if config.appendfilename.exists() {
+                aof::replay(&self.shared, &config.appendfilename).await?;
+            }
+            self.shared.aof.open(&config.appendfilename)?;
+        } else if config.dbfilename.exists() {
+            rdb::load(&self.shared.store, &config.dbfilename)?;
+        }
+        let listener = TcpListener::bind(config.addr()).await?;
+        let unix_listener = config.unixsocket.as_deref().map(bind_unix).transpose()?;
+        let tls = match (&config.tls_cert_file, &config.tls_key_file) {
+            (Some(cert_file), Some(key_file)) => Some(tls::acceptor(cert_file, key_file)?),
+            (None, None) => None,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "tls-cert-file and tls-key-file must be set together",
+                ));
+            }
+        };
 
-        signal.notify_one();
+        let expiry_cycle = self.shared.store.spawn_expiry_cycle(EXPIRY_CYCLE_INTERVAL);
+        let fsync_cycle = Aof::spawn_fsync_cycle(&self.shared);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut connections = JoinSet::new();
+        ready.notify_one();
 
         loop {
-            let (mut socket, _) = listener.accept().await?;
-            tokio::spawn(async move {
-                let mut buf = [0; 1024];
-                let n = socket.read(&mut buf).await.unwrap();
-                println!("Received message : {}", String::from_utf8_lossy(&buf[..n]));
-                socket
-                    .write_all("awesome-message".as_bytes())
-                    .await
-                    .unwrap();
-            });
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (socket, addr) = accepted?;
+                    self.accept(&mut connections, socket, addr.to_string(), tls.as_ref(), &shutdown_rx).await;
+                }
+                accepted = accept_unix(unix_listener.as_ref()) => {
+                    let (socket, addr) = accepted?;
+                    self.accept(&mut connections, socket, addr, None, &shutdown_rx).await;
+                }
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                _ = shutdown.notified() => break,
+            }
         }
+
+        drop(listener);
+        drop(unix_listener);
+        if let Some(path) = &config.unixsocket {
+            let _ = std::fs::remove_file(path);
+        }
+        shutdown_tx.send_replace(true);
+        while connections.join_next().await.is_some() {}
+        expiry_cycle.abort();
+        if let Some(fsync_cycle) = fsync_cycle {
+            fsync_cycle.abort();
+        }
+        self.shared.aof.sync()?;
+
+        Ok(())
+    }
+
+    async fn accept<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
+        &self,
+        connections: &mut JoinSet<()>,
+        mut socket: S,
+        addr: String,
+        tls: Option<&TlsAcceptor>,
+        shutdown: &watch::Receiver<bool>,
+    ) {
+        if self.shared.clients.len() >= self.shared.config().max_clients {
+            warn!(peer = %addr, "rejecting connection: max number of clients reached");
+            if tls.is_none() {
+                let reply = RespMessage::Error("ERR max number of clients reached".to_string());
+                let _ = socket.write_all(&reply.encode()).await;
+            }
+            return;
+        }
+
+        let shared = self.shared.clone();
+        let client = Client {
+            addr,
+            ..Client::new(shared.stats.next_client_id.fetch_add(1, Ordering::Relaxed))
+        };
+        let span = info_span!("connection", id = client.id, peer = %client.addr);
+        let registration = shared.clients.register(client.id, client.addr.clone());
+        let shutdown = shutdown.clone();
+        let stats = shared.stats.clone();
+        let tls = tls.cloned();
+        let connection = async move {
+            info!("accepted connection");
+            stats.connected_clients.fetch_add(1, Ordering::Relaxed);
+            let result = match tls {
+                Some(tls) => match tls.accept(socket).await {
+                    Ok(socket) => handle_connection(socket, shared, client, shutdown).await,
+                    Err(e) => Err(e),
+                },
+                None => handle_connection(socket, shared, client, shutdown).await,
+            };
+            if let Err(e) = result {
+                warn!(error = %e, "connection error");
+            }
+            stats.connected_clients.fetch_sub(1, Ordering::Relaxed);
+            drop(registration);
+            info!("closed connection");
+        };
+        connections.spawn(connection.instrument(span));
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+#[cfg(unix)]
+async fn accept_unix(listener: Option<&UnixListener>) -> io::Result<(UnixStream, String)> {
+    let Some(listener) = listener else {
+        return std::future::pending().await;
+    };
+
+    let (socket, _) = listener.accept().await?;
+    let path = listener.local_addr()?;
+    let path = path.as_pathname().unwrap_or(Path::new(""));
+    Ok((socket, format!("{}:0", path.display())))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_: &Path) -> io::Result<TcpListener> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "UNIX sockets are not supported on this platform",
+    ))
+}
+
+#[cfg(not(unix))]
+async fn accept_unix(_: Option<&TcpListener>) -> io::Result<(tokio::net::TcpStream, String)> {
+    std::future::pending().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::Notify,
+    };
+    use tokio_rustls::rustls;
+
+    use crate::{
+        config::Config,
+        rdb,
+        server::Server,
+        store::{Store, Value},
+    };
+
+    fn test_config() -> Config {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        Config {
+            port,
+            dbfilename: std::env::temp_dir().join(format!("redis-clone-{}.rdb", port)),
+            ..Config::default()
+        }
+    }
+
+    async fn start_server() -> String {
+        start_server_with(test_config()).await
+    }
+
+    async fn start_server_with(config: Config) -> String {
+        let addr = config.addr();
+        let server = Server::new(config);
+        let ready = Arc::new(Notify::new());
+        let ready_for_server = ready.clone();
+
+        tokio::spawn(async move {
+            server.listen_with_signal(ready_for_server).await.unwrap();
+        });
+        ready.notified().await;
+
+        addr
+    }
+
+    async fn request(stream: &mut TcpStream, data: &[u8]) -> Vec<u8> {
+        stream.write_all(data).await.unwrap();
+
+        let mut response = [0; 1024];
+        let n = stream.read(&mut response).await.unwrap();
+        response[..n].to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut stream, b"*1\r\n$4\r\nPING\r\n").await,
+            b"+PONG\r\n"
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_lifecycle_is_logged() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        request(&mut stream, b"PING\r\n").await;
+        drop(stream);
+        for _ in 0..100 {
+            if String::from_utf8_lossy(&logs.0.lock().unwrap()).contains("closed connection") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let accepted = logs
+            .lines()
+            .find(|line| line.contains("accepted connection"))
+            .unwrap_or_else(|| panic!("{}", logs));
+        assert!(accepted.contains("connection{id=1 peer=127.0.0.1:"));
+        assert!(logs.contains("closed connection"), "{}", logs);
+    }
+
+    #[tokio::test]
+    async fn test_client_list_shows_connected_clients() {
+        let addr = start_server().await;
+        let mut first = TcpStream::connect(&addr).await.unwrap();
+        let mut second = TcpStream::connect(&addr).await.unwrap();
+        request(&mut second, b"CLIENT SETNAME second\r\n").await;
+
+        let list = request(&mut first, b"CLIENT LIST\r\n").await;
+        let list = String::from_utf8(list).unwrap();
+
+        let lines: Vec<_> = list
+            .lines()
+            .skip(1)
+            .filter(|line| !line.is_empty())
+            .collect();
+        assert_eq!(lines.len(), 2, "{:?}", list);
+        assert!(lines[0].starts_with("id=1 "));
+        assert!(lines[1].starts_with("id=2 "));
+        assert!(lines[1].contains(" name=second "));
+        assert!(lines[0].contains(&format!(" addr={} ", first.local_addr().unwrap())));
+        assert!(lines[1].contains(&format!(" addr={} ", second.local_addr().unwrap())));
+
+        drop(second);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let list = request(&mut first, b"CLIENT LIST\r\n").await;
+        assert!(!String::from_utf8(list).unwrap().contains("id=2 "));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ping_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("redis-clone-{}.sock", std::process::id()));
+        let config = Config {
+            unixsocket: Some(path.clone()),
+            ..test_config()
+        };
+        start_server_with(config).await;
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"PING\r\n").await.unwrap();
+        let mut response = [0; 7];
+        stream.read_exact(&mut response).await.unwrap();
+
+        assert_eq!(&response, b"+PONG\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_ping_over_tls() {
+        let rcgen::CertifiedKey { cert, signing_key } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let config = test_config();
+        let cert_file = std::env::temp_dir().join(format!("redis-clone-{}.crt", config.port));
+        let key_file = std::env::temp_dir().join(format!("redis-clone-{}.key", config.port));
+        std::fs::write(&cert_file, cert.pem()).unwrap();
+        std::fs::write(&key_file, signing_key.serialize_pem()).unwrap();
+        let addr = start_server_with(Config {
+            tls_cert_file: Some(cert_file.clone()),
+            tls_key_file: Some(key_file.clone()),
+            ..config
+        })
+        .await;
+        std::fs::remove_file(cert_file).unwrap();
+        std::fs::remove_file(key_file).unwrap();
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert.der().clone()).unwrap();
+        let client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+        let socket = TcpStream::connect(&addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(server_name, socket).await.unwrap();
+
+        stream.write_all(b"PING\r\n").await.unwrap();
+        let mut response = [0; 7];
+        stream.read_exact(&mut response).await.unwrap();
+
+        assert_eq!(&response, b"+PONG\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_commands() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        stream
+            .write_all(b"SET foo bar\r\nGET foo\r\nPING\r\n")
+            .await
+            .unwrap();
+        let expected = b"+OK\r\n$3\r\nbar\r\n+PONG\r\n";
+        let mut response = vec![0; expected.len()];
+        stream.read_exact(&mut response).await.unwrap();
+
+        assert_eq!(response, expected);
+    }
+
+    #[tokio::test]
+    async fn test_debug_sleep_delays_reply() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let started = std::time::Instant::now();
+        assert_eq!(
+            request(&mut stream, b"DEBUG SLEEP 0.1\r\n").await,
+            b"+OK\r\n"
+        );
+        assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_blpop_is_woken_by_push() {
+        let addr = start_server().await;
+        let mut blocked = TcpStream::connect(&addr).await.unwrap();
+        let mut pusher = TcpStream::connect(&addr).await.unwrap();
+
+        blocked.write_all(b"BLPOP empty list 0\r\n").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(
+            request(&mut pusher, b"LPUSH list hello\r\n").await,
+            b":1\r\n"
+        );
+
+        let mut response = [0; 1024];
+        let n = blocked.read(&mut response).await.unwrap();
+        assert_eq!(&response[..n], b"*2\r\n$4\r\nlist\r\n$5\r\nhello\r\n");
+        assert_eq!(request(&mut pusher, b"LLEN list\r\n").await, b":0\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_blpop_is_woken_by_rename_and_copy() {
+        let addr = start_server().await;
+        let mut blocked = TcpStream::connect(&addr).await.unwrap();
+        let mut writer = TcpStream::connect(&addr).await.unwrap();
+
+        for (command, reply) in [
+            (&b"RENAME source list\r\n"[..], &b"+OK\r\n"[..]),
+            (b"COPY source list\r\n", b":1\r\n"),
+        ] {
+            request(&mut writer, b"RPUSH source a\r\n").await;
+            blocked.write_all(b"BLPOP list copied 5\r\n").await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            assert_eq!(request(&mut writer, command).await, reply);
+
+            let mut response = [0; 1024];
+            let n = tokio::time::timeout(
+                std::time::Duration::from_secs(1),
+                blocked.read(&mut response),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(&response[..n], b"*2\r\n$4\r\nlist\r\n$1\r\na\r\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_brpop_times_out() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let started = std::time::Instant::now();
+        assert_eq!(
+            request(&mut stream, b"BRPOP list 0.1\r\n").await,
+            b"$-1\r\n"
+        );
+        assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_echo() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut stream, b"*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n").await,
+            b"$5\r\nhello\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multiple_commands_on_one_connection() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_eq!(request(&mut stream, b"PING\r\n").await, b"+PONG\r\n");
+        assert_eq!(request(&mut stream, b"ECHO hi\r\n").await, b"$2\r\nhi\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error_closes_connection() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"*1\r\n$abc\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(
+            response,
+            b"-ERR Protocol error: Invalid bulk string length\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_and_get_across_connections() {
+        let addr = start_server().await;
+        let mut writer = TcpStream::connect(&addr).await.unwrap();
+        let mut reader = TcpStream::connect(&addr).await.unwrap();
+
+        assert_eq!(
+            request(
+                &mut writer,
+                b"*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\nbar\r\n"
+            )
+            .await,
+            b"+OK\r\n"
+        );
+        assert_eq!(
+            request(&mut reader, b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n").await,
+            b"$3\r\nbar\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loads_snapshot_on_startup() {
+        let config = test_config();
+        let store = Store::default();
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        rdb::save(&store, &config.dbfilename).unwrap();
+
+        let path = config.dbfilename.clone();
+        let addr = start_server_with(config).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(request(&mut stream, b"GET foo\r\n").await, b"$3\r\nbar\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_server() {
+        let config = test_config();
+        let addr = config.addr();
+        let server = Server::new(config);
+        let ready = Arc::new(Notify::new());
+        let shutdown = Arc::new(Notify::new());
+
+        let handle = tokio::spawn({
+            let ready = ready.clone();
+            let shutdown = shutdown.clone();
+            async move { server.listen_with_shutdown(ready, shutdown).await }
+        });
+        ready.notified().await;
+
+        let mut idle_client = TcpStream::connect(&addr).await.unwrap();
+        assert_eq!(request(&mut idle_client, b"PING\r\n").await, b"+PONG\r\n");
+
+        shutdown.notify_one();
+        let result = tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(result.is_ok());
+        assert!(TcpStream::connect(&addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_clients_rejects_extra_connections() {
+        let config = Config {
+            max_clients: 1,
+            ..test_config()
+        };
+        let addr = start_server_with(config).await;
+
+        let mut first = TcpStream::connect(&addr).await.unwrap();
+        assert_eq!(request(&mut first, b"PING\r\n").await, b"+PONG\r\n");
+
+        let mut second = TcpStream::connect(&addr).await.unwrap();
+        let mut response = Vec::new();
+        second.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response, b"-ERR max number of clients reached\r\n");
+
+        drop(first);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let mut third = TcpStream::connect(&addr).await.unwrap();
+        assert_eq!(request(&mut third, b"PING\r\n").await, b"+PONG\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_max_clients_admits_reconnect_after_disconnect() {
+        let config = Config {
+            max_clients: 1,
+            ..test_config()
+        };
+        let addr = start_server_with(config).await;
+
+        for _ in 0..20 {
+            let mut stream = TcpStream::connect(&addr).await.unwrap();
+            assert_eq!(request(&mut stream, b"PING\r\n").await, b"+PONG\r\n");
+            assert_eq!(request(&mut stream, b"QUIT\r\n").await, b"+OK\r\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_client_is_disconnected() {
+        let config = Config {
+            timeout: std::time::Duration::from_millis(100),
+            ..test_config()
+        };
+        let addr = start_server_with(config).await;
+        let mut stream = TcpStream::connect(&addr).await.unwrap();
+        assert_eq!(request(&mut stream, b"PING\r\n").await, b"+PONG\r\n");
+
+        let mut response = Vec::new();
+        let read = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            stream.read_to_end(&mut response),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(read.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_subscriber() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut subscriber, b"SUBSCRIBE news\r\n").await,
+            b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news hello\r\n").await,
+            b":1\r\n"
+        );
+
+        let mut response = [0; 1024];
+        let n = subscriber.read(&mut response).await.unwrap();
+        assert_eq!(
+            &response[..n],
+            b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resp3_subscriber_receives_push_frames() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        assert!(
+            request(&mut subscriber, b"HELLO 3\r\n")
+                .await
+                .starts_with(b"%")
+        );
+        assert_eq!(
+            request(&mut subscriber, b"SUBSCRIBE news\r\n").await,
+            b">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(request(&mut subscriber, b"PING\r\n").await, b"+PONG\r\n");
+        assert_eq!(request(&mut subscriber, b"GET missing\r\n").await, b"_\r\n");
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news hello\r\n").await,
+            b":1\r\n"
+        );
+
+        let mut response = [0; 1024];
+        let n = subscriber.read(&mut response).await.unwrap();
+        assert_eq!(
+            &response[..n],
+            b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quit_closes_connection() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(&addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut stream, b"SUBSCRIBE\r\n").await,
+            b"-ERR wrong number of arguments for 'subscribe' command\r\n"
+        );
+        assert_eq!(request(&mut stream, b"QUIT\r\n").await, b"+OK\r\n");
+
+        let mut response = Vec::new();
+        assert_eq!(stream.read_to_end(&mut response).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ping_in_subscriber_mode() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+
+        request(&mut subscriber, b"SUBSCRIBE news\r\n").await;
+        assert_eq!(
+            request(&mut subscriber, b"PING\r\n").await,
+            b"*2\r\n$4\r\npong\r\n$0\r\n\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, b"PING hi\r\n").await,
+            b"*2\r\n$4\r\npong\r\n$2\r\nhi\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_frame_survives_publish() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        request(&mut subscriber, b"SUBSCRIBE news\r\n").await;
+        subscriber
+            .write_all(b"*2\r\n$4\r\nPING\r\n$2")
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news hello\r\n").await,
+            b":1\r\n"
+        );
+
+        let mut response = [0; 1024];
+        let n = subscriber.read(&mut response).await.unwrap();
+        assert_eq!(
+            &response[..n],
+            b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, b"\r\nhi\r\n").await,
+            b"*2\r\n$4\r\npong\r\n$2\r\nhi\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_pattern_subscriber() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut subscriber, b"PSUBSCRIBE news.*\r\n").await,
+            b"*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:1\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news.tech hello\r\n").await,
+            b":1\r\n"
+        );
+
+        let mut response = [0; 1024];
+        let n = subscriber.read(&mut response).await.unwrap();
+        assert_eq!(
+            &response[..n],
+            b"*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$9\r\nnews.tech\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, b"PUNSUBSCRIBE\r\n").await,
+            b"*3\r\n$12\r\npunsubscribe\r\n$6\r\nnews.*\r\n:0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reset_unsubscribes() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        request(&mut subscriber, b"SUBSCRIBE news\r\n").await;
+        assert_eq!(request(&mut subscriber, b"RESET\r\n").await, b"+RESET\r\n");
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news hello\r\n").await,
+            b":0\r\n"
+        );
+        assert_eq!(request(&mut subscriber, b"GET foo\r\n").await, b"$-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_confirmation() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+
+        assert_eq!(
+            request(&mut subscriber, b"SUBSCRIBE news sports\r\n").await,
+            b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$6\r\nsports\r\n:2\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, b"UNSUBSCRIBE news\r\n").await,
+            b"*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, b"UNSUBSCRIBE\r\n").await,
+            b"*3\r\n$11\r\nunsubscribe\r\n$6\r\nsports\r\n:0\r\n"
+        );
+        assert_eq!(request(&mut subscriber, b"GET foo\r\n").await, b"$-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_commands_restricted_in_subscriber_mode() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+
+        request(&mut subscriber, b"SUBSCRIBE news\r\n").await;
+
+        assert_eq!(
+            request(&mut subscriber, b"GET foo\r\n").await,
+            b"-ERR Can't execute 'get': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_subscriber_is_dropped() {
+        let addr = start_server().await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        request(&mut subscriber, b"SUBSCRIBE news\r\n").await;
+        drop(subscriber);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        assert_eq!(
+            request(&mut publisher, b"PUBLISH news hello\r\n").await,
+            b":0\r\n"
+        );
     }
 }
@@ -0,0 +1,118 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+};
+
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortedSet {
+    scores: HashMap<Vec<u8>, f64>,
+    ordered: BTreeSet<(Score, Vec<u8>)>,
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        SortedSet::default()
+    }
+
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> bool {
+        let score = score + 0.0;
+        let previous = self.scores.insert(member.clone(), score);
+        if let Some(previous) = previous {
+            self.ordered.remove(&(Score(previous), member.clone()));
+        }
+        self.ordered.insert((Score(score), member));
+        previous.is_none()
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.ordered.remove(&(Score(score), member.to_vec()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, f64)> {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sorted_set::SortedSet;
+
+    fn members(set: &SortedSet) -> Vec<&[u8]> {
+        set.iter().map(|(member, _)| member.as_slice()).collect()
+    }
+
+    #[test]
+    fn test_orders_by_score_then_member() {
+        let mut set = SortedSet::new();
+        set.insert(b"b".to_vec(), 1.0);
+        set.insert(b"c".to_vec(), 0.5);
+        set.insert(b"a".to_vec(), 1.0);
+
+        assert_eq!(members(&set), vec![b"c".as_slice(), b"a", b"b"]);
+    }
+
+    #[test]
+    fn test_insert_updates_score() {
+        let mut set = SortedSet::new();
+
+        assert!(set.insert(b"a".to_vec(), 1.0));
+        assert!(set.insert(b"b".to_vec(), 2.0));
+        assert!(!set.insert(b"a".to_vec(), 3.0));
+
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.score(b"a"), Some(3.0));
+        assert_eq!(members(&set), vec![b"b".as_slice(), b"a"]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = SortedSet::new();
+        set.insert(b"a".to_vec(), 1.0);
+
+        assert!(set.remove(b"a"));
+        assert!(!set.remove(b"a"));
+        assert!(set.is_empty());
+        assert_eq!(set.iter().count(), 0);
+    }
+}
@@ -0,0 +1,1312 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState},
+    sync::{
+        Arc, Mutex, MutexGuard, OnceLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rand::{
+    Rng,
+    seq::{IndexedRandom, SliceRandom, index},
+};
+use tokio::task::JoinHandle;
+
+use crate::{aof::Aof, blocking::Blocking, sorted_set::SortedSet};
+
+pub const DEFAULT_DATABASES: usize = 16;
+
+const SHARDS: usize = 16;
+const SHARD_BITS: u32 = SHARDS.trailing_zeros();
+const SCAN_SHARD_SHIFT: u32 = u64::BITS - SHARD_BITS;
+
+const EXPIRY_SAMPLE_SIZE: usize = 20;
+const EXPIRY_REPEAT_THRESHOLD: usize = EXPIRY_SAMPLE_SIZE / 4;
+const EXPIRY_MAX_ROUNDS: usize = 16;
+const ACCESS_RESOLUTION: Duration = Duration::from_millis(10);
+const ENTRY_OVERHEAD: usize = 64;
+const EVICTION_SAMPLE_SIZE: usize = 5;
+const RANDOM_KEY_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemberHasher;
+
+impl BuildHasher for MemberHasher {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        static STATE: OnceLock<RandomState> = OnceLock::new();
+        STATE.get_or_init(RandomState::new).build_hasher()
+    }
+}
+
+pub type Members = HashSet<Vec<u8>, MemberHasher>;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(HashMap<Vec<u8>, Vec<u8>>),
+    Set(Members),
+    SortedSet(SortedSet),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::SortedSet(_) => "zset",
+        }
+    }
+
+    pub fn estimated_size(&self) -> usize {
+        match self {
+            Value::String(value) => value.len(),
+            Value::List(list) => list.iter().map(Vec::len).sum(),
+            Value::Hash(hash) => hash
+                .iter()
+                .map(|(field, value)| field.len() + value.len())
+                .sum(),
+            Value::Set(set) => set.iter().map(Vec::len).sum(),
+            Value::SortedSet(zset) => zset
+                .iter()
+                .map(|(member, _)| member.len() + size_of::<f64>())
+                .sum(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DatabaseStats {
+    pub keys: usize,
+    pub expires: usize,
+    pub memory: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StoreStats {
+    pub databases: Vec<DatabaseStats>,
+}
+
+impl StoreStats {
+    pub fn memory(&self) -> usize {
+        self.databases.iter().map(|db| db.memory).sum()
+    }
+}
+
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+    version: u64,
+    last_accessed: Instant,
+    size: usize,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+#[derive(Default)]
+struct KeyIndex {
+    keys: Vec<Vec<u8>>,
+    slots: HashMap<Vec<u8>, usize>,
+}
+
+impl KeyIndex {
+    fn insert(&mut self, key: &[u8]) {
+        if !self.slots.contains_key(key) {
+            self.slots.insert(key.to_vec(), self.keys.len());
+            self.keys.push(key.to_vec());
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let Some(slot) = self.slots.remove(key) else {
+            return;
+        };
+        self.keys.swap_remove(slot);
+        if let Some(moved) = self.keys.get(slot) {
+            self.slots.insert(moved.clone(), slot);
+        }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R, amount: usize) -> impl Iterator<Item = &Vec<u8>> {
+        index::sample(rng, self.keys.len(), amount.min(self.keys.len()))
+            .into_iter()
+            .map(|slot| &self.keys[slot])
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.slots.clear();
+    }
+}
+
+#[derive(Default)]
+struct Shard {
+    entries: HashMap<Vec<u8>, Entry>,
+    scan_order: BTreeMap<u64, Vec<Vec<u8>>>,
+    keys: KeyIndex,
+    volatile: KeyIndex,
+    clock: u64,
+    memory: usize,
+    dirty: Vec<Vec<u8>>,
+    expired: Vec<Vec<u8>>,
+    ready: Vec<Vec<u8>>,
+}
+
+impl Shard {
+    fn get(&mut self, key: &[u8]) -> Option<&Value> {
+        self.live_entry(key).map(|entry| &entry.value)
+    }
+
+    fn get_mut(&mut self, key: &[u8]) -> Option<&mut Value> {
+        self.dirty.push(key.to_vec());
+        self.ready.push(key.to_vec());
+        self.modified_entry(key).map(|entry| &mut entry.value)
+    }
+
+    fn get_or_insert_with(&mut self, key: &[u8], default: impl FnOnce() -> Value) -> &mut Value {
+        if self.live_entry(key).is_none() {
+            self.set(key.to_vec(), default());
+        }
+
+        self.get_mut(key).unwrap()
+    }
+
+    fn set(&mut self, key: Vec<u8>, value: Value) {
+        self.insert(
+            key,
+            Entry {
+                value,
+                expires_at: None,
+                version: 0,
+                last_accessed: Instant::now(),
+                size: 0,
+            },
+        );
+    }
+
+    fn insert(&mut self, key: Vec<u8>, mut entry: Entry) {
+        self.clock += 1;
+        entry.version = self.clock;
+        entry.last_accessed = Instant::now();
+        entry.size = entry_size(&key, &entry.value);
+        self.memory += entry.size;
+        match entry.expires_at {
+            Some(_) => self.volatile.insert(&key),
+            None => self.volatile.remove(&key),
+        }
+        if matches!(entry.value, Value::List(_)) {
+            self.ready.push(key.clone());
+        }
+        match self.entries.insert(key.clone(), entry) {
+            Some(replaced) => self.memory -= replaced.size,
+            None => {
+                self.keys.insert(&key);
+                self.scan_order
+                    .entry(scan_hash(&key))
+                    .or_default()
+                    .push(key);
+            }
+        }
+    }
+
+    fn discard(&mut self, key: &[u8]) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.memory -= entry.size;
+        self.keys.remove(key);
+        self.volatile.remove(key);
+        let hash = scan_hash(key);
+        if let Some(keys) = self.scan_order.get_mut(&hash) {
+            keys.retain(|scanned| scanned != key);
+            if keys.is_empty() {
+                self.scan_order.remove(&hash);
+            }
+        }
+        Some(entry)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.scan_order.clear();
+        self.keys.clear();
+        self.volatile.clear();
+        self.dirty.clear();
+        self.expired.clear();
+        self.ready.clear();
+        self.memory = 0;
+    }
+
+    fn settle(&mut self) {
+        for key in std::mem::take(&mut self.dirty) {
+            if let Some(entry) = self.entries.get_mut(&key) {
+                let size = entry_size(&key, &entry.value);
+                self.memory = self.memory - entry.size + size;
+                entry.size = size;
+            }
+        }
+    }
+
+    fn exists(&mut self, key: &[u8]) -> bool {
+        self.live_entry(key).is_some()
+    }
+
+    fn expire(&mut self, key: &[u8], ttl: Duration) -> bool {
+        let Some(deadline) = Instant::now().checked_add(ttl) else {
+            return false;
+        };
+        match self.modified_entry(key) {
+            Some(entry) => {
+                entry.expires_at = Some(deadline);
+                self.volatile.insert(key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn persist(&mut self, key: &[u8]) -> bool {
+        if self
+            .live_entry(key)
+            .is_none_or(|entry| entry.expires_at.is_none())
+        {
+            return false;
+        }
+
+        let persisted = self
+            .modified_entry(key)
+            .is_some_and(|entry| entry.expires_at.take().is_some());
+        self.volatile.remove(key);
+        persisted
+    }
+
+    fn version(&mut self, key: &[u8]) -> Option<u64> {
+        self.unexpired_entry(key).map(|entry| entry.version)
+    }
+
+    fn idle_time(&mut self, key: &[u8]) -> Option<Duration> {
+        self.unexpired_entry(key)
+            .map(|entry| entry.last_accessed.elapsed())
+    }
+
+    fn ttl(&mut self, key: &[u8]) -> Option<Option<Duration>> {
+        self.unexpired_entry(key).map(|entry| {
+            entry
+                .expires_at
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    fn remove(&mut self, key: &[u8]) -> bool {
+        let existed = self.live_entry(key).is_some();
+        self.discard(key);
+        existed
+    }
+
+    fn take(&mut self, key: &[u8]) -> Option<Entry> {
+        self.live_entry(key)?;
+        self.discard(key)
+    }
+
+    fn modified_entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        self.clock += 1;
+        let version = self.clock;
+        let entry = self.live_entry(key)?;
+        entry.version = version;
+        Some(entry)
+    }
+
+    fn live_entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        let now = Instant::now();
+        let entry = self.entry_at(key, now)?;
+        if now.saturating_duration_since(entry.last_accessed) >= ACCESS_RESOLUTION {
+            entry.last_accessed = now;
+        }
+        Some(entry)
+    }
+
+    fn unexpired_entry(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        self.entry_at(key, Instant::now())
+    }
+
+    fn entry_at(&mut self, key: &[u8], now: Instant) -> Option<&mut Entry> {
+        if self.entries.get(key)?.is_expired(now) {
+            self.discard(key);
+            self.expired.push(key.to_vec());
+            return None;
+        }
+
+        self.entries.get_mut(key)
+    }
+
+    fn evict_expired_sample(&mut self) -> usize {
+        let now = Instant::now();
+
+        let expired: Vec<Vec<u8>> = self
+            .volatile
+            .sample(&mut rand::rng(), EXPIRY_SAMPLE_SIZE)
+            .filter(|key| self.entries[*key].is_expired(now))
+            .cloned()
+            .collect();
+
+        for key in &expired {
+            self.discard(key);
+        }
+
+        let count = expired.len();
+        self.expired.extend(expired);
+        count
+    }
+
+    fn random_key<R: Rng>(&self, rng: &mut R) -> Option<Vec<u8>> {
+        self.keys.keys.choose(rng).cloned()
+    }
+
+    fn soonest_expiring_key<R: Rng>(&self, rng: &mut R) -> Option<Vec<u8>> {
+        self.volatile
+            .sample(rng, EVICTION_SAMPLE_SIZE)
+            .min_by_key(|key| self.entries[*key].expires_at)
+            .cloned()
+    }
+}
+
+fn entry_size(key: &[u8], value: &Value) -> usize {
+    key.len() + value.estimated_size() + ENTRY_OVERHEAD
+}
+
+fn log_expired(aof: &Aof, db: usize, shard: &mut Shard) {
+    for key in std::mem::take(&mut shard.expired) {
+        if let Err(e) = aof.append(db, &[b"DEL".to_vec(), key]) {
+            tracing::error!(error = %e, "AOF write failed");
+        }
+    }
+}
+
+fn record_memory(used_memory: &AtomicUsize, before: usize, after: usize) {
+    if after >= before {
+        used_memory.fetch_add(after - before, Ordering::Relaxed);
+    } else {
+        used_memory.fetch_sub(before - after, Ordering::Relaxed);
+    }
+}
+
+pub struct Keyspace<'a> {
+    db: usize,
+    shards: Vec<Option<MutexGuard<'a, Shard>>>,
+    used_memory: &'a AtomicUsize,
+    locked_memory: usize,
+    aof: &'a Aof,
+    blocking: &'a Blocking,
+}
+
+impl<'a> Keyspace<'a> {
+    fn new(store: &'a Store, db: usize, shards: Vec<Option<MutexGuard<'a, Shard>>>) -> Self {
+        let locked_memory = shards.iter().flatten().map(|shard| shard.memory).sum();
+        Keyspace {
+            db,
+            shards,
+            used_memory: &store.used_memory,
+            locked_memory,
+            aof: &store.aof,
+            blocking: &store.blocking,
+        }
+    }
+}
+
+impl Drop for Keyspace<'_> {
+    fn drop(&mut self) {
+        self.log_expired();
+        self.wake_ready();
+        let mut memory = 0;
+        for shard in self.shards.iter_mut().flatten() {
+            shard.settle();
+            memory += shard.memory;
+        }
+        record_memory(self.used_memory, self.locked_memory, memory);
+    }
+}
+
+impl Keyspace<'_> {
+    pub fn get(&mut self, key: &[u8]) -> Option<&Value> {
+        self.shard(key).get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut Value> {
+        self.shard(key).get_mut(key)
+    }
+
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &[u8],
+        default: impl FnOnce() -> Value,
+    ) -> &mut Value {
+        self.shard(key).get_or_insert_with(key, default)
+    }
+
+    pub fn set(&mut self, key: Vec<u8>, value: Value) {
+        self.shard(&key).set(key, value);
+    }
+
+    pub fn exists(&mut self, key: &[u8]) -> bool {
+        self.shard(key).exists(key)
+    }
+
+    pub fn expire(&mut self, key: &[u8], ttl: Duration) -> bool {
+        self.shard(key).expire(key, ttl)
+    }
+
+    pub fn persist(&mut self, key: &[u8]) -> bool {
+        self.shard(key).persist(key)
+    }
+
+    pub fn ttl(&mut self, key: &[u8]) -> Option<Option<Duration>> {
+        self.shard(key).ttl(key)
+    }
+
+    pub fn version(&mut self, key: &[u8]) -> Option<u64> {
+        self.shard(key).version(key)
+    }
+
+    pub fn idle_time(&mut self, key: &[u8]) -> Option<Duration> {
+        self.shard(key).idle_time(key)
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        self.shard(key).remove(key)
+    }
+
+    pub fn rename(&mut self, from: &[u8], to: &[u8]) -> bool {
+        let Some(entry) = self.shard(from).take(from) else {
+            return false;
+        };
+
+        self.shard(to).insert(to.to_vec(), entry);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.locked_mut().for_each(Shard::clear);
+    }
+
+    pub fn log_expired(&mut self) {
+        for shard in self.shards.iter_mut().flatten() {
+            log_expired(self.aof, self.db, shard);
+        }
+    }
+
+    fn wake_ready(&mut self) {
+        let now = Instant::now();
+        for shard in self.shards.iter_mut().flatten() {
+            for key in std::mem::take(&mut shard.ready) {
+                if shard.entries.get(&key).is_some_and(|entry| {
+                    matches!(entry.value, Value::List(_)) && !entry.is_expired(now)
+                }) {
+                    self.blocking.wake(self.db, &key);
+                }
+            }
+        }
+    }
+
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<Vec<u8>>) {
+        let index = scan_shard(cursor);
+        let shard = self.shards[index]
+            .as_deref()
+            .expect("shard for cursor is not locked");
+        let now = Instant::now();
+        let start = (cursor << SHARD_BITS) | index as u64;
+
+        let mut keys = Vec::new();
+        let mut hashes = shard.scan_order.range(start..);
+        for (&hash, bucket) in hashes.by_ref() {
+            keys.extend(
+                bucket
+                    .iter()
+                    .filter(|key| !shard.entries[*key].is_expired(now))
+                    .cloned(),
+            );
+            if keys.len() >= count {
+                let position = (hash >> SHARD_BITS) + 1;
+                if position < 1 << SCAN_SHARD_SHIFT && hashes.next().is_some() {
+                    return ((index as u64) << SCAN_SHARD_SHIFT | position, keys);
+                }
+                break;
+            }
+        }
+
+        let next_cursor = match index + 1 {
+            SHARDS => 0,
+            next => (next as u64) << SCAN_SHARD_SHIFT,
+        };
+        (next_cursor, keys)
+    }
+
+    pub fn keys(&self) -> Vec<&Vec<u8>> {
+        self.iter().map(|(key, _, _)| key).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &Value, Option<Duration>)> {
+        let now = Instant::now();
+        self.locked()
+            .flat_map(|shard| &shard.entries)
+            .filter(move |(_, entry)| !entry.is_expired(now))
+            .map(move |(key, entry)| {
+                let ttl = entry
+                    .expires_at
+                    .map(|deadline| deadline.saturating_duration_since(now));
+                (key, &entry.value, ttl)
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn random_key<R: Rng>(&self, rng: &mut R) -> Option<Vec<u8>> {
+        let now = Instant::now();
+        let total: usize = self.locked().map(|shard| shard.entries.len()).sum();
+        if total == 0 {
+            return None;
+        }
+
+        for _ in 0..RANDOM_KEY_ATTEMPTS {
+            let mut slot = rng.random_range(..total);
+            let shard = self.locked().find(|shard| {
+                let found = slot < shard.entries.len();
+                if !found {
+                    slot -= shard.entries.len();
+                }
+                found
+            })?;
+            let key = &shard.keys.keys[slot];
+            if !shard.entries[key].is_expired(now) {
+                return Some(key.clone());
+            }
+        }
+
+        self.iter().map(|(key, _, _)| key.clone()).next()
+    }
+
+    pub fn expires(&self) -> usize {
+        self.iter().filter(|(_, _, ttl)| ttl.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard(&mut self, key: &[u8]) -> &mut Shard {
+        self.shards[shard_index(key)]
+            .as_mut()
+            .expect("shard for key is not locked")
+    }
+
+    fn locked(&self) -> impl Iterator<Item = &Shard> {
+        self.shards
+            .iter()
+            .map(|shard| shard.as_deref().expect("every shard must be locked"))
+    }
+
+    fn locked_mut(&mut self) -> impl Iterator<Item = &mut Shard> {
+        self.shards
+            .iter_mut()
+            .map(|shard| shard.as_deref_mut().expect("every shard must be locked"))
+    }
+}
+
+fn scan_hash(key: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn scan_shard(cursor: u64) -> usize {
+    (cursor >> SCAN_SHARD_SHIFT) as usize
+}
+
+fn shard_index(key: &[u8]) -> usize {
+    (scan_hash(key) % SHARDS as u64) as usize
+}
+
+type Database = Vec<Mutex<Shard>>;
+
+#[derive(Clone)]
+pub struct Store {
+    databases: Arc<Vec<Database>>,
+    active_expire: Arc<AtomicBool>,
+    used_memory: Arc<AtomicUsize>,
+    aof: Arc<Aof>,
+    blocking: Blocking,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store::new(DEFAULT_DATABASES)
+    }
+}
+
+impl Store {
+    pub fn new(databases: usize) -> Self {
+        Store {
+            databases: Arc::new(
+                (0..databases)
+                    .map(|_| (0..SHARDS).map(|_| Mutex::default()).collect())
+                    .collect(),
+            ),
+            active_expire: Arc::new(AtomicBool::new(true)),
+            used_memory: Arc::default(),
+            aof: Arc::default(),
+            blocking: Blocking::default(),
+        }
+    }
+
+    pub fn with_aof(mut self, aof: Arc<Aof>) -> Self {
+        self.aof = aof;
+        self
+    }
+
+    pub fn with_blocking(mut self, blocking: Blocking) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    pub fn used_memory(&self) -> usize {
+        self.used_memory.load(Ordering::Relaxed)
+    }
+
+    pub fn evict_random<R: Rng>(&self, rng: &mut R) -> Option<(usize, Vec<u8>)> {
+        self.evict_with(rng, Shard::random_key)
+    }
+
+    pub fn evict_volatile_ttl<R: Rng>(&self, rng: &mut R) -> Option<(usize, Vec<u8>)> {
+        self.evict_with(rng, Shard::soonest_expiring_key)
+    }
+
+    fn evict_with<R: Rng>(
+        &self,
+        rng: &mut R,
+        select: impl Fn(&Shard, &mut R) -> Option<Vec<u8>>,
+    ) -> Option<(usize, Vec<u8>)> {
+        let mut candidates: Vec<(usize, usize)> = (0..self.databases.len())
+            .flat_map(|db| (0..SHARDS).map(move |index| (db, index)))
+            .collect();
+        candidates.shuffle(rng);
+
+        for (db, index) in candidates {
+            let mut shard = self.databases[db][index].lock().unwrap();
+            let Some(key) = select(&shard, rng) else {
+                continue;
+            };
+            let before = shard.memory;
+            shard.discard(&key);
+            record_memory(&self.used_memory, before, shard.memory);
+            return Some((db, key));
+        }
+
+        None
+    }
+
+    pub fn idle_time(&self, db: usize, key: &[u8]) -> Option<u64> {
+        self.lock_keys(db, &[key])
+            .idle_time(key)
+            .map(|idle| idle.as_secs())
+    }
+
+    pub fn set_active_expire(&self, enabled: bool) {
+        self.active_expire.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn databases(&self) -> usize {
+        self.databases.len()
+    }
+
+    pub fn stats(&self) -> StoreStats {
+        let now = Instant::now();
+        let databases = self
+            .databases
+            .iter()
+            .map(|shards| {
+                let mut stats = DatabaseStats::default();
+                for shard in shards {
+                    let shard = shard.lock().unwrap();
+                    stats.memory += shard.memory;
+                    for entry in shard.entries.values() {
+                        if entry.is_expired(now) {
+                            continue;
+                        }
+                        stats.keys += 1;
+                        stats.expires += entry.expires_at.is_some() as usize;
+                    }
+                }
+                stats
+            })
+            .collect();
+
+        StoreStats { databases }
+    }
+
+    pub fn lock(&self, db: usize) -> Keyspace<'_> {
+        Keyspace::new(
+            self,
+            db,
+            self.databases[db]
+                .iter()
+                .map(|shard| Some(shard.lock().unwrap()))
+                .collect(),
+        )
+    }
+
+    pub fn lock_keys(&self, db: usize, keys: &[&[u8]]) -> Keyspace<'_> {
+        let mut indices: Vec<usize> = keys.iter().map(|key| shard_index(key)).collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let mut shards: Vec<Option<MutexGuard<'_, Shard>>> = (0..SHARDS).map(|_| None).collect();
+        for index in indices {
+            shards[index] = Some(self.databases[db][index].lock().unwrap());
+        }
+
+        Keyspace::new(self, db, shards)
+    }
+
+    pub fn lock_cursor(&self, db: usize, cursor: u64) -> Keyspace<'_> {
+        let index = scan_shard(cursor);
+        let mut shards: Vec<Option<MutexGuard<'_, Shard>>> = (0..SHARDS).map(|_| None).collect();
+        shards[index] = Some(self.databases[db][index].lock().unwrap());
+
+        Keyspace::new(self, db, shards)
+    }
+
+    pub fn spawn_expiry_cycle(&self, interval: Duration) -> JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if !store.active_expire.load(Ordering::Relaxed) {
+                    continue;
+                }
+                for (db, shard) in store
+                    .databases
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(db, shards)| shards.iter().map(move |shard| (db, shard)))
+                {
+                    for _ in 0..EXPIRY_MAX_ROUNDS {
+                        let mut shard = shard.lock().unwrap();
+                        let before = shard.memory;
+                        let expired = shard.evict_expired_sample();
+                        log_expired(&store.aof, db, &mut shard);
+                        record_memory(&store.used_memory, before, shard.memory);
+                        if expired <= EXPIRY_REPEAT_THRESHOLD {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        time::Duration,
+    };
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::store::{DatabaseStats, Store, Value, shard_index};
+
+    fn stored(store: &Store, key: &[u8]) -> bool {
+        store.databases[0][shard_index(key)]
+            .lock()
+            .unwrap()
+            .entries
+            .contains_key(key)
+    }
+
+    #[test]
+    fn test_get_missing_key() {
+        let store = Store::default();
+
+        assert_eq!(store.lock(0).get(b"foo"), None);
+    }
+
+    #[test]
+    fn test_version_changes_on_write() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        assert_eq!(keyspace.version(b"foo"), None);
+
+        keyspace.set(b"foo".to_vec(), Value::String(b"1".to_vec()));
+        let created = keyspace.version(b"foo");
+        keyspace.get(b"foo");
+        assert_eq!(keyspace.version(b"foo"), created);
+
+        keyspace.expire(b"foo", Duration::from_secs(100));
+        assert_ne!(keyspace.version(b"foo"), created);
+        keyspace.remove(b"foo");
+        assert_eq!(keyspace.version(b"foo"), None);
+    }
+
+    #[test]
+    fn test_store_idle_time_in_seconds() {
+        let store = Store::default();
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert_eq!(store.idle_time(0, b"foo"), Some(0));
+        assert_eq!(store.idle_time(0, b"missing"), None);
+    }
+
+    #[test]
+    fn test_idle_time_resets_on_access() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(keyspace.idle_time(b"foo").unwrap() >= Duration::from_millis(30));
+        keyspace.ttl(b"foo");
+        assert!(keyspace.idle_time(b"foo").unwrap() >= Duration::from_millis(30));
+        keyspace.get(b"foo");
+        assert!(keyspace.idle_time(b"foo").unwrap() < Duration::from_millis(30));
+        assert_eq!(keyspace.idle_time(b"missing"), None);
+    }
+
+    #[test]
+    fn test_used_memory_follows_writes() {
+        let store = Store::new(2);
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        let single = store.used_memory();
+        assert!(single >= 6);
+
+        let mut keyspace = store.lock_keys(1, &[b"list"]);
+        match keyspace.get_or_insert_with(b"list", || Value::List(Default::default())) {
+            Value::List(list) => list.push_back(vec![0; 1000]),
+            _ => unreachable!(),
+        }
+        drop(keyspace);
+        assert!(store.used_memory() >= single + 1000);
+
+        store.lock(1).clear();
+        assert_eq!(store.used_memory(), single);
+        store.lock(0).remove(b"foo");
+        assert_eq!(store.used_memory(), 0);
+    }
+
+    #[test]
+    fn test_evict_volatile_ttl_skips_persistent_keys() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"persistent".to_vec(), Value::String(b"1".to_vec()));
+        keyspace.set(b"volatile".to_vec(), Value::String(b"1".to_vec()));
+        keyspace.expire(b"volatile", Duration::from_secs(100));
+        drop(keyspace);
+
+        let mut rng = rand::rng();
+        assert_eq!(
+            store.evict_volatile_ttl(&mut rng),
+            Some((0, b"volatile".to_vec()))
+        );
+        assert_eq!(store.evict_volatile_ttl(&mut rng), None);
+        assert_eq!(
+            store.evict_random(&mut rng),
+            Some((0, b"persistent".to_vec()))
+        );
+        assert_eq!(store.used_memory(), 0);
+    }
+
+    #[test]
+    fn test_stats_counts_keys_per_database() {
+        let store = Store::new(2);
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"a".to_vec(), Value::String(b"12345".to_vec()));
+        keyspace.set(b"b".to_vec(), Value::String(b"1".to_vec()));
+        keyspace.expire(b"b", Duration::from_secs(100));
+        keyspace.set(b"expired".to_vec(), Value::String(b"1".to_vec()));
+        keyspace.expire(b"expired", Duration::from_millis(10));
+        drop(keyspace);
+        std::thread::sleep(Duration::from_millis(30));
+
+        let stats = store.stats();
+
+        assert_eq!(
+            stats.databases,
+            vec![
+                DatabaseStats {
+                    keys: 2,
+                    expires: 1,
+                    memory: 208,
+                },
+                DatabaseStats::default(),
+            ]
+        );
+        assert_eq!(stats.memory(), store.used_memory());
+    }
+
+    #[test]
+    fn test_set_then_get() {
+        let store = Store::default();
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert_eq!(
+            store.lock(0).get(b"foo"),
+            Some(&Value::String(b"bar".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_clones_share_data() {
+        let store = Store::default();
+        store
+            .clone()
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert_eq!(
+            store.lock(0).get(b"foo"),
+            Some(&Value::String(b"bar".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_ttl() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert_eq!(keyspace.ttl(b"missing"), None);
+        assert_eq!(keyspace.ttl(b"foo"), Some(None));
+
+        assert!(keyspace.expire(b"foo", Duration::from_secs(10)));
+        let remaining = keyspace.ttl(b"foo").unwrap().unwrap();
+        assert!(remaining > Duration::from_secs(9) && remaining <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_expire_overflow_is_rejected() {
+        let store = Store::default();
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert!(!store.lock(0).expire(b"foo", Duration::MAX));
+        assert_eq!(store.lock(0).ttl(b"foo"), Some(None));
+    }
+
+    #[test]
+    fn test_key_expires() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        keyspace.expire(b"foo", Duration::from_millis(20));
+
+        std::thread::sleep(Duration::from_millis(40));
+
+        assert!(!keyspace.exists(b"foo"));
+        assert_eq!(keyspace.get(b"foo"), None);
+        assert_eq!(keyspace.ttl(b"foo"), None);
+    }
+
+    #[test]
+    fn test_set_clears_expiry() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        keyspace.expire(b"foo", Duration::from_secs(10));
+        keyspace.set(b"foo".to_vec(), Value::String(b"baz".to_vec()));
+
+        assert_eq!(keyspace.ttl(b"foo"), Some(None));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_cycle_evicts_unread_keys() {
+        let store = Store::default();
+        {
+            let mut keyspace = store.lock(0);
+            keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+            keyspace.set(b"persistent".to_vec(), Value::String(b"bar".to_vec()));
+            keyspace.expire(b"foo", Duration::from_millis(20));
+        }
+
+        let cycle = store.spawn_expiry_cycle(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cycle.abort();
+
+        assert!(!stored(&store, b"foo"));
+        assert!(stored(&store, b"persistent"));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_cycle_paused() {
+        let store = Store::default();
+        store.set_active_expire(false);
+        {
+            let mut keyspace = store.lock(0);
+            keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+            keyspace.expire(b"foo", Duration::from_millis(20));
+        }
+
+        let cycle = store.spawn_expiry_cycle(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cycle.abort();
+
+        assert!(stored(&store, b"foo"));
+        assert!(store.lock(0).get(b"foo").is_none());
+    }
+
+    #[test]
+    fn test_scan_visits_every_key() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        for i in 0..50 {
+            keyspace.set(format!("key:{}", i).into_bytes(), Value::String(vec![]));
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = keyspace.scan(cursor, 7);
+            seen.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        seen.sort();
+        seen.dedup();
+
+        assert_eq!(seen.len(), 50);
+    }
+
+    #[test]
+    fn test_scan_locks_only_the_cursor_shard() {
+        let store = Store::default();
+        let other = (0..)
+            .map(|i| format!("key:{}", i).into_bytes())
+            .find(|key| shard_index(key) != 0)
+            .unwrap();
+
+        let scanning = store.lock_cursor(0, 0);
+        store
+            .lock_keys(0, &[&other])
+            .set(other.clone(), Value::String(vec![]));
+        drop(scanning);
+
+        assert!(stored(&store, &other));
+    }
+
+    #[test]
+    fn test_scan_returns_keys_present_throughout() {
+        let store = Store::default();
+        let stable: Vec<Vec<u8>> = (0..50)
+            .map(|i| format!("stable:{}", i).into_bytes())
+            .collect();
+        for key in &stable {
+            store.lock(0).set(key.clone(), Value::String(vec![]));
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        let mut round = 0;
+        loop {
+            let (next, keys) = store.lock_cursor(0, cursor).scan(cursor, 3);
+            seen.extend(keys);
+            let mut keyspace = store.lock(0);
+            keyspace.set(
+                format!("added:{}", round).into_bytes(),
+                Value::String(vec![]),
+            );
+            keyspace.remove(format!("added:{}", round / 2).as_bytes());
+            round += 1;
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        for key in &stable {
+            assert!(seen.contains(key));
+        }
+    }
+
+    #[test]
+    fn test_databases_are_isolated() {
+        let store = Store::new(2);
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert_eq!(store.databases(), 2);
+        assert!(store.lock(0).exists(b"foo"));
+        assert!(!store.lock(1).exists(b"foo"));
+    }
+
+    #[test]
+    fn test_rename_keeps_ttl() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        keyspace.expire(b"foo", Duration::from_secs(100));
+
+        assert!(keyspace.rename(b"foo", b"baz"));
+        assert!(!keyspace.exists(b"foo"));
+        assert!(keyspace.ttl(b"baz").unwrap().is_some());
+        assert!(!keyspace.rename(b"missing", b"other"));
+    }
+
+    #[test]
+    fn test_persist_survives_expiry_cycle() {
+        let store = Store::default();
+        store
+            .lock(0)
+            .set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+        store.lock(0).expire(b"foo", Duration::from_millis(10));
+
+        assert!(store.lock(0).persist(b"foo"));
+        assert!(!store.lock(0).persist(b"foo"));
+        std::thread::sleep(Duration::from_millis(30));
+        store.databases[0][shard_index(b"foo")]
+            .lock()
+            .unwrap()
+            .evict_expired_sample();
+
+        assert!(stored(&store, b"foo"));
+        assert_eq!(store.lock(0).ttl(b"foo"), Some(None));
+    }
+
+    #[test]
+    fn test_expiry_sample_only_visits_volatile_keys() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        for i in 0..1000 {
+            keyspace.set(
+                format!("key:{i}").into_bytes(),
+                Value::String(b"1".to_vec()),
+            );
+        }
+        for i in 0..3 {
+            keyspace.expire(format!("key:{i}").as_bytes(), Duration::from_millis(10));
+        }
+        keyspace.persist(b"key:2");
+        keyspace.remove(b"key:1");
+        drop(keyspace);
+
+        let volatile: usize = store.databases[0]
+            .iter()
+            .map(|shard| shard.lock().unwrap().volatile.keys.len())
+            .sum();
+        assert_eq!(volatile, 1);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(
+            store.databases[0][shard_index(b"key:0")]
+                .lock()
+                .unwrap()
+                .evict_expired_sample(),
+            1
+        );
+        assert!(!stored(&store, b"key:0"));
+    }
+
+    #[test]
+    fn test_random_key_reaches_every_live_key() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        for key in ["a", "b", "c", "expired"] {
+            keyspace.set(key.as_bytes().to_vec(), Value::String(b"1".to_vec()));
+        }
+        keyspace.expire(b"expired", Duration::from_millis(10));
+        std::thread::sleep(Duration::from_millis(30));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let drawn: HashSet<Vec<u8>> = (0..200)
+            .map(|_| keyspace.random_key(&mut rng).unwrap())
+            .collect();
+        assert_eq!(
+            drawn,
+            HashSet::from([b"a".to_vec(), b"b".to_vec(), b"c".to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_random_key_is_uniform_across_shards() {
+        let store = Store::default();
+        let mut keyspace = store.lock(0);
+        let mut keys: Vec<Vec<u8>> = (0..)
+            .map(|i| format!("crowded:{i}").into_bytes())
+            .filter(|key| shard_index(key) == 0)
+            .take(15)
+            .collect();
+        keys.extend(
+            (0..)
+                .map(|i| format!("alone:{i}").into_bytes())
+                .find(|key| shard_index(key) == 1),
+        );
+        for key in &keys {
+            keyspace.set(key.clone(), Value::String(b"1".to_vec()));
+        }
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut drawn: HashMap<Vec<u8>, usize> = HashMap::new();
+        for _ in 0..16_000 {
+            *drawn
+                .entry(keyspace.random_key(&mut rng).unwrap())
+                .or_default() += 1;
+        }
+        for key in &keys {
+            assert!((800..1200).contains(&drawn[key]));
+        }
+    }
+
+    #[test]
+    fn test_rename_across_shards() {
+        let store = Store::default();
+        let other = (0..)
+            .map(|i| format!("key:{}", i).into_bytes())
+            .find(|key| shard_index(key) != shard_index(b"foo"))
+            .unwrap();
+        let mut keyspace = store.lock_keys(0, &[b"foo", &other]);
+        keyspace.set(b"foo".to_vec(), Value::String(b"bar".to_vec()));
+
+        assert!(keyspace.rename(b"foo", &other));
+        assert_eq!(keyspace.get(&other), Some(&Value::String(b"bar".to_vec())));
+        assert!(!keyspace.exists(b"foo"));
+    }
+
+    #[test]
+    fn test_independent_keys_lock_in_parallel() {
+        let store = Store::default();
+        let other = (0..)
+            .map(|i| format!("key:{}", i).into_bytes())
+            .find(|key| shard_index(key) != shard_index(b"foo"))
+            .unwrap();
+        let _held = store.lock_keys(0, &[b"foo"]);
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let writer = store.clone();
+        std::thread::spawn(move || {
+            writer
+                .lock_keys(0, &[&other])
+                .set(other.clone(), Value::String(b"x".to_vec()));
+            done_tx.send(()).unwrap();
+        });
+
+        assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
@@ -0,0 +1,30 @@
+use std::{io, path::Path, sync::Arc};
+
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+};
+
+pub fn acceptor(cert_file: &Path, key_file: &Path) -> io::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert_file)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid_data(cert_file, e))?;
+    let key = PrivateKeyDer::from_pem_file(key_file).map_err(|e| invalid_data(key_file, e))?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn invalid_data(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid PEM file {} : {}", path.display(), e),
+    )
+}